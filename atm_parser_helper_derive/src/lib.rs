@@ -0,0 +1,130 @@
+//! Derive macros for the `Eoi` and `IsEoi` traits of `atm_parser_helper`. Use them via the
+//! `derive` feature of that crate rather than depending on this crate directly.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Variant};
+
+/// Implements `Eoi` for an enum by returning one of its unit variants.
+///
+/// The variant can be marked with `#[eoi]`. Without such an attribute, the variant named `Eoi` or
+/// `UnexpectedEndOfInput` is used. Either way, the variant must not have any fields.
+///
+/// ```
+/// use atm_parser_helper::{Eoi, ParserHelper};
+///
+/// #[derive(Eoi, Debug, PartialEq)]
+/// enum MyError {
+///     #[eoi]
+///     Truncated,
+///     Invalid,
+/// }
+///
+/// let mut p = ParserHelper::new(b"");
+/// assert_eq!(p.next::<MyError>().unwrap_err().e, MyError::Truncated);
+/// ```
+#[proc_macro_derive(Eoi, attributes(eoi))]
+pub fn derive_eoi(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let expanded = eoi_variant_of(&input).map(|variant| {
+        let name = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::atm_parser_helper::Eoi for #name #ty_generics #where_clause {
+                fn eoi() -> Self {
+                    #name::#variant
+                }
+            }
+        }
+    });
+    match expanded {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Implements `IsEoi` for an enum by checking for the same variant that `#[derive(Eoi)]` uses.
+///
+/// ```
+/// use atm_parser_helper::{Eoi, IsEoi, ParserHelper};
+///
+/// #[derive(Eoi, IsEoi, Debug, PartialEq)]
+/// enum MyError {
+///     #[eoi]
+///     Truncated,
+///     Invalid,
+/// }
+///
+/// let mut p = ParserHelper::new(b"");
+/// let err = p.optional_or_err(|p| p.next::<MyError>()).unwrap_err();
+/// assert_eq!(err.e, MyError::Truncated);
+/// assert!(!MyError::Invalid.is_eoi());
+/// ```
+#[proc_macro_derive(IsEoi, attributes(eoi))]
+pub fn derive_is_eoi(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let expanded = eoi_variant_of(&input).map(|variant| {
+        let name = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::atm_parser_helper::IsEoi for #name #ty_generics #where_clause {
+                fn is_eoi(&self) -> bool {
+                    ::core::matches!(self, #name::#variant)
+                }
+            }
+        }
+    });
+    match expanded {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The name of the end of input variant of the enum `input`.
+fn eoi_variant_of(input: &DeriveInput) -> syn::Result<&Ident> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`Eoi` can only be derived for enums",
+            ))
+        }
+    };
+
+    let variant = eoi_variant(data.variants.iter())?;
+    if !matches!(variant.fields, Fields::Unit) {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "the end of input variant must not have any fields",
+        ));
+    }
+    Ok(&variant.ident)
+}
+
+/// Picks the variant marked with `#[eoi]`, or else the one with a conventional name.
+fn eoi_variant<'v>(
+    mut variants: impl Iterator<Item = &'v Variant> + Clone,
+) -> syn::Result<&'v Variant> {
+    let mut marked = variants
+        .clone()
+        .filter(|v| v.attrs.iter().any(|a| a.path().is_ident("eoi")));
+    if let Some(variant) = marked.next() {
+        if let Some(other) = marked.next() {
+            return Err(syn::Error::new_spanned(
+                other,
+                "only one variant can be marked with `#[eoi]`",
+            ));
+        }
+        return Ok(variant);
+    }
+
+    variants
+        .find(|v| v.ident == "Eoi" || v.ident == "UnexpectedEndOfInput")
+        .ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                "mark the end of input variant with `#[eoi]`, or name it `Eoi` or `UnexpectedEndOfInput`",
+            )
+        })
+}
@@ -0,0 +1,11 @@
+use atm_parser_helper::Eoi;
+
+#[derive(Eoi)]
+enum MyError {
+    #[eoi]
+    Truncated,
+    #[eoi]
+    Invalid,
+}
+
+fn main() {}
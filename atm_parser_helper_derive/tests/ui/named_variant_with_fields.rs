@@ -0,0 +1,9 @@
+use atm_parser_helper::Eoi;
+
+#[derive(Eoi)]
+enum MyError {
+    UnexpectedEndOfInput { needed: usize },
+    Invalid,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use atm_parser_helper::Eoi;
+
+#[derive(Eoi)]
+struct MyError {
+    position: usize,
+}
+
+fn main() {}
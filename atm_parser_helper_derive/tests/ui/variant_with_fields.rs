@@ -0,0 +1,10 @@
+use atm_parser_helper::Eoi;
+
+#[derive(Eoi)]
+enum MyError {
+    #[eoi]
+    Truncated(usize),
+    Invalid,
+}
+
+fn main() {}
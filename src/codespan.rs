@@ -0,0 +1,97 @@
+//! Conversion of parse errors into `codespan_reporting` diagnostics.
+//!
+//! The diagnostics refer to a single file with the file id `()`, use `source_file` to obtain a
+//! matching file for rendering them. The name of the input is passed to `source_file` rather than
+//! to `emit_error`: a codespan `Diagnostic` only refers to files by id, and the renderer takes the
+//! name to display from the file.
+//!
+//! ```
+//! use atm_parser_helper::codespan::{emit_error, source_file};
+//! use atm_parser_helper::{Error, MiniError};
+//! use codespan_reporting::term::{self, termcolor::NoColor};
+//!
+//! let input = b"a = \xff";
+//! let err = Error::new(4, MiniError::msg("expected a value"));
+//!
+//! let mut out = NoColor::new(Vec::new());
+//! let config = term::Config::default();
+//! let file = source_file("foo.toml", input);
+//! term::emit_to_write_style(&mut out, &config, &file, &emit_error(&err, input)).unwrap();
+//! assert!(String::from_utf8(out.into_inner()).unwrap().contains("foo.toml:1:5"));
+//! ```
+use std::fmt::Display;
+use std::ops::Range;
+
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::SimpleFile;
+
+use crate::{Error, ErrorContext};
+
+/// Create a file for rendering diagnostics about `input`. Invalid UTF-8 is replaced byte by byte
+/// with `?`, so that the byte offsets of errors stay valid.
+pub fn source_file(name: &str, input: &[u8]) -> SimpleFile<String, String> {
+    SimpleFile::new(name.to_string(), to_text(input))
+}
+
+/// Create a diagnostic for `err`, which should have been produced from `input`, with a primary
+/// label at the error position.
+pub fn emit_error<E: Display>(err: &Error<E>, input: &[u8]) -> Diagnostic<()> {
+    error_diagnostic(err, &to_text(input))
+}
+
+/// Same as `emit_error`, but additionally labels the start of every item in the context of the
+/// error with a secondary label.
+pub fn emit_error_context<E: Display>(err: &ErrorContext<E>, input: &[u8]) -> Diagnostic<()> {
+    let text = to_text(input);
+    error_diagnostic(&err.error, &text).with_labels(
+        err.context
+            .iter()
+            .map(|(label, position)| {
+                Label::secondary((), label_range(&text, position))
+                    .with_message(format!("while parsing {}", label))
+            })
+            .collect(),
+    )
+}
+
+/// Create a diagnostic for `err` with a primary label at the error position in `text`.
+fn error_diagnostic<E: Display>(err: &Error<E>, text: &str) -> Diagnostic<()> {
+    Diagnostic::error()
+        .with_message(&err.e)
+        .with_label(Label::primary((), label_range(text, err.position)))
+}
+
+/// Convert `input` to a string of the same length, by replacing every byte that is not part of
+/// valid UTF-8 with `?`.
+fn to_text(input: &[u8]) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut rest = input;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return text;
+            }
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                text.push_str(&String::from_utf8_lossy(valid));
+                let invalid_len = err.error_len().unwrap_or(invalid.len());
+                text.extend(std::iter::repeat_n('?', invalid_len));
+                rest = &invalid[invalid_len..];
+            }
+        }
+    }
+}
+
+/// The range of the character at `position` in `text`, or an empty range at the end of `text`.
+fn label_range(text: &str, position: usize) -> Range<usize> {
+    let mut start = position.min(text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let end = text[start..]
+        .chars()
+        .next()
+        .map_or(start, |c| start + c.len_utf8());
+    start..end
+}
@@ -1,14 +1,72 @@
 //! Some simple utilities for hand-coded parsers.
+//!
+//! Enable the `memchr` feature to speed up the methods that search the input for delimiters, and
+//! the `derive` feature for `#[derive(Eoi)]`. The `codespan-reporting` feature enables the
+//! `codespan` module for rendering errors with the crate of the same name, and the `miette`
+//! feature enables the `miette` module for reporting errors through `miette`.
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
 use std::slice::SliceIndex;
 
 use thiserror::Error;
 
+#[cfg(feature = "derive")]
+pub use atm_parser_helper_derive::{Eoi, IsEoi};
+
+#[cfg(feature = "codespan-reporting")]
+pub mod codespan;
+#[cfg(feature = "miette")]
+pub mod miette;
+
 /// A trait for error types with a variant that indicates that the end of the parsed input has been
 /// reached unexpectedly.
 pub trait Eoi {
     /// Create the instance of the error type thatnotes an unexpected end of input.
     fn eoi() -> Self;
+
+    /// Create the instance of the error type that notes an unexpected end of input, where `needed`
+    /// more bytes of input would have been required. Defaults to `eoi()`.
+    fn eoi_needing(needed: usize) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = needed;
+        Self::eoi()
+    }
+}
+
+/// A trait for error types that can tell whether an instance indicates that the end of the parsed
+/// input has been reached unexpectedly. Required by methods that treat truncated input differently
+/// from other failures, such as `ParserHelper::optional_or_err`.
+pub trait IsEoi {
+    /// Whether this instance notes an unexpected end of input, i.e., whether it could have been
+    /// created by `Eoi::eoi` or `Eoi::eoi_needing`.
+    fn is_eoi(&self) -> bool;
+}
+
+/// A trait for error types with a variant that indicates that an unexpected byte has been
+/// encountered.
+pub trait Unexpected {
+    /// Create the instance of the error type that notes the unexpected byte `found`.
+    fn unexpected(found: u8) -> Self;
+}
+
+/// A trait for error types with a variant that indicates that a repeated parser succeeded without
+/// consuming any input, so that repeating it would never terminate.
+pub trait NoProgress {
+    /// Create the instance of the error type that notes a repeated parser making no progress.
+    fn no_progress() -> Self;
+}
+
+/// A trait for error types with a variant that indicates that a parsed number does not fit into
+/// its type.
+pub trait Overflow {
+    /// Create the instance of the error type that notes a numeric overflow.
+    fn overflow() -> Self;
 }
 
 /// Wraps a slice of input bytes to provide methods for advancing through the input, tracking
@@ -18,6 +76,22 @@ pub struct ParserHelper<'a> {
     position: usize,
 }
 
+/// A saved position of a `ParserHelper`, obtained via `ParserHelper::mark`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Mark<'a> {
+    position: usize,
+    input: PhantomData<&'a [u8]>,
+    #[cfg(debug_assertions)]
+    input_address: usize,
+}
+
+impl<'a> Mark<'a> {
+    /// The saved position.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
 /// A parse error, tagging an arbitrary error type with an input position.
 #[derive(Error, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 #[error("parse error at position {position}: {e}")]
@@ -34,6 +108,109 @@ impl<E> Error<E> {
             e,
         }
     }
+
+    /// Convert the inner error, keeping the position. Combine with `Result::map_err` to convert
+    /// the error of a parse result: `result.map_err(|err| err.map(MyError::from))`.
+    pub fn map<F, E2>(self, f: F) -> Error<E2>
+    where
+        F: FnOnce(E) -> E2,
+    {
+        Error::new(self.position, f(self.e))
+    }
+
+    /// Attach a note to this error, such as a hint on how to fix the input.
+    pub fn with_note(self, note: String) -> AnnotatedError<E> {
+        AnnotatedError { error: self, note }
+    }
+
+    /// Attach the name of the input to this error. Static names do not allocate.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError};
+    ///
+    /// let err = Error::new(12, MiniError::msg("expected a key"));
+    /// assert_eq!(err.to_string(), "parse error at position 12: expected a key");
+    /// assert_eq!(
+    ///     err.with_source_name("foo.toml").to_string(),
+    ///     "foo.toml: parse error at position 12: expected a key",
+    /// );
+    /// ```
+    pub fn with_source_name(self, name: impl Into<Cow<'static, str>>) -> NamedError<E> {
+        NamedError {
+            name: name.into(),
+            error: self,
+        }
+    }
+
+    /// The line and column of the error position, looked up in the `LineIndex` of the input the
+    /// error was produced from.
+    pub fn locate(&self, index: &LineIndex) -> (usize, usize) {
+        index.location(self.position)
+    }
+
+    /// Display this error together with the input bytes surrounding its position, with the byte
+    /// at the error position enclosed in square brackets. `input` should be the input the error
+    /// was produced from.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError};
+    ///
+    /// let err = Error::new(4, MiniError::msg("expected a digit"));
+    /// assert_eq!(
+    ///     err.display_with_context(b"[1, x, 3]").to_string(),
+    ///     "parse error at position 4: expected a digit\n    [1, [x], 3]",
+    /// );
+    /// ```
+    pub fn display_with_context<'e>(&'e self, input: &'e [u8]) -> ErrorWithContext<'e, E> {
+        ErrorWithContext { error: self, input }
+    }
+
+    /// Display this error together with the line of input containing its position, with a caret
+    /// under the byte at the error position. Bytes other than printable ASCII are hex-escaped,
+    /// and long lines are truncated around the error position. `input` should be the input the
+    /// error was produced from.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError};
+    ///
+    /// let input = b"a = 1\nb = ?\n";
+    /// let err = Error::new(10, MiniError::msg("expected a value"));
+    /// assert_eq!(
+    ///     err.display_with_input(input).to_string(),
+    ///     "parse error at position 10: expected a value\n2 | b = ?\n  |     ^",
+    /// );
+    ///
+    /// let err = Error::new(12, MiniError::Eoi);
+    /// assert_eq!(
+    ///     err.display_with_input(input).to_string(),
+    ///     "parse error at position 12: unexpected end of input\n3 | \n  | ^",
+    /// );
+    ///
+    /// let input = b"[0, 1, 2, 3, \xff, 5, 6, 7, 8]";
+    /// let err = Error::new(13, MiniError::msg("expected a digit"));
+    /// assert_eq!(
+    ///     err.display_with_input(input).max_context(6).to_string(),
+    ///     "parse error at position 13: expected a digit\n\
+    ///      1 | ...2, 3, \\xff, 5, 6...\n  |          ^",
+    /// );
+    /// ```
+    pub fn display_with_input<'e>(&'e self, input: &'e [u8]) -> Report<'e, E> {
+        Report {
+            error: self,
+            input,
+            max_context: 40,
+        }
+    }
+
+    /// Attach the line and column of the error position within `input`, which should be the input
+    /// the error was produced from. This scans the input up to the error position.
+    pub fn to_located(self, input: &[u8]) -> LocatedError<E> {
+        LocatedError {
+            position: self.position,
+            line_col: position_to_line_col(input, self.position),
+            e: self.e,
+        }
+    }
 }
 
 impl<E: serde::de::Error> serde::de::Error for Error<E> {
@@ -42,6 +219,510 @@ impl<E: serde::de::Error> serde::de::Error for Error<E> {
     }
 }
 
+/// Displays an error together with the surrounding input. Obtained via
+/// `Error::display_with_context`.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorWithContext<'e, E> {
+    error: &'e Error<E>,
+    input: &'e [u8],
+}
+
+impl<'e, E: fmt::Display> fmt::Display for ErrorWithContext<'e, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CONTEXT: usize = 8;
+
+        let position = self.error.position.min(self.input.len());
+        let before = &self.input[position.saturating_sub(CONTEXT)..position];
+        writeln!(f, "{}", self.error)?;
+        write!(f, "    {}", before.escape_ascii())?;
+        match self.input.get(position) {
+            Some(b) => {
+                let end = (position + 1 + CONTEXT).min(self.input.len());
+                let after = &self.input[position + 1..end];
+                write!(f, "[{}]{}", b.escape_ascii(), after.escape_ascii())
+            }
+            None => write!(f, "[end of input]"),
+        }
+    }
+}
+
+/// Displays an error together with the line of input it occurred in. Obtained via
+/// `Error::display_with_input`.
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'e, E> {
+    error: &'e Error<E>,
+    input: &'e [u8],
+    max_context: usize,
+}
+
+impl<'e, E> Report<'e, E> {
+    /// Set the maximal number of bytes to show on either side of the error position. Defaults to
+    /// 40.
+    pub fn max_context(mut self, max_context: usize) -> Self {
+        self.max_context = max_context;
+        self
+    }
+}
+
+impl<'e, E: fmt::Display> fmt::Display for Report<'e, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let input = self.input;
+        let position = self.error.position.min(input.len());
+
+        let line_start = input[..position].iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let mut line_end = input[position..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(input.len(), |i| position + i);
+        if line_end > position && input[line_end - 1] == b'\r' {
+            line_end -= 1;
+        }
+
+        let start = line_start.max(position.saturating_sub(self.max_context));
+        let end = line_end.min(position.saturating_add(self.max_context).saturating_add(1));
+
+        let mut snippet = String::new();
+        if start > line_start {
+            snippet.push_str("...");
+        }
+        push_escaped(&mut snippet, &input[start..position]);
+        let caret = snippet.len();
+        push_escaped(&mut snippet, &input[position..end]);
+        if end < line_end {
+            snippet.push_str("...");
+        }
+
+        let line = position_to_line_col(input, position).line.to_string();
+        writeln!(f, "{}", self.error)?;
+        writeln!(f, "{} | {}", line, snippet)?;
+        write!(f, "{:gutter$} | {:caret$}^", "", "", gutter = line.len(), caret = caret)
+    }
+}
+
+/// Append `bytes` to `out`, hex-escaping everything but printable ASCII.
+fn push_escaped(out: &mut String, bytes: &[u8]) {
+    for b in bytes {
+        if b.is_ascii_graphic() || *b == b' ' {
+            out.push(char::from(*b));
+        } else {
+            out.push_str(&format!("\\x{:02x}", b));
+        }
+    }
+}
+
+/// A parse error, tagging an arbitrary error type with a range of input positions.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct ErrorSpan<E> {
+    pub start: usize,
+    pub end: usize,
+    pub e: E,
+}
+
+impl<E> From<Error<E>> for ErrorSpan<E> {
+    fn from(err: Error<E>) -> Self {
+        ErrorSpan {
+            start: err.position,
+            end: err.position,
+            e: err.e,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ErrorSpan<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "parse error at position {}: {}", self.start, self.e)
+        } else {
+            write!(f, "parse error at {}..{}: {}", self.start, self.end, self.e)
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ErrorSpan<E> {}
+
+/// A parse error together with a stack of labels describing what was being parsed when it
+/// occurred. Each label is paired with the position at which parsing the labeled item began.
+/// Obtained via `ParserHelper::within`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ErrorContext<E> {
+    pub error: Error<E>,
+    pub context: ContextFrames,
+}
+
+impl<E> From<Error<E>> for ErrorContext<E> {
+    fn from(error: Error<E>) -> Self {
+        ErrorContext {
+            error,
+            context: ContextFrames::new(),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ErrorContext<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, position) in self.context.iter() {
+            write!(f, "while parsing {} (at {}): ", label, position)?;
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ErrorContext<E> {}
+
+const INLINE_FRAMES: usize = 4;
+
+/// The `(label, position)` frames of an `ErrorContext`. Up to four frames are stored inline, only
+/// deeper nesting allocates.
+#[derive(PartialEq, Eq, Hash, Clone, Default)]
+pub struct ContextFrames {
+    // The innermost frames, in the order they were added.
+    inline: [(&'static str, usize); INLINE_FRAMES],
+    inline_len: usize,
+    // The frames that did not fit inline, in the order they were added.
+    outer: Vec<(&'static str, usize)>,
+}
+
+impl ContextFrames {
+    /// Create an empty stack of frames.
+    pub fn new() -> Self {
+        ContextFrames::default()
+    }
+
+    /// Add a frame that encloses all frames added so far.
+    pub fn push_outer(&mut self, label: &'static str, position: usize) {
+        if self.inline_len < INLINE_FRAMES {
+            self.inline[self.inline_len] = (label, position);
+            self.inline_len += 1;
+        } else {
+            self.outer.push((label, position));
+        }
+    }
+
+    /// The number of frames.
+    pub fn len(&self) -> usize {
+        self.inline_len + self.outer.len()
+    }
+
+    /// Whether there are no frames.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the frames, from the outermost to the innermost.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.outer
+            .iter()
+            .rev()
+            .chain(self.inline[..self.inline_len].iter().rev())
+            .copied()
+    }
+}
+
+impl fmt::Debug for ContextFrames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A parse error together with a supplementary note for humans. Obtained via `Error::with_note`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct AnnotatedError<E> {
+    pub error: Error<E>,
+    pub note: String,
+}
+
+impl<E> From<AnnotatedError<E>> for Error<E> {
+    fn from(err: AnnotatedError<E>) -> Self {
+        err.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for AnnotatedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (note: {})", self.error, self.note)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for AnnotatedError<E> {}
+
+/// A parse error together with the name of the input it occurred in, such as a file name.
+/// Obtained via `Error::with_source_name`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct NamedError<E> {
+    pub name: Cow<'static, str>,
+    pub error: Error<E>,
+}
+
+impl<E> From<NamedError<E>> for Error<E> {
+    fn from(err: NamedError<E>) -> Self {
+        err.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for NamedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.error)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for NamedError<E> {}
+
+/// Keeps the error that got furthest into the input out of all errors recorded with it. Useful
+/// for reporting a meaningful error after trying several alternatives.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FurthestError<E> {
+    furthest: Option<Error<E>>,
+}
+
+impl<E> FurthestError<E> {
+    /// Create a tracker that has not recorded any error yet.
+    pub fn new() -> Self {
+        FurthestError { furthest: None }
+    }
+
+    /// Keep `err` if its position is greater than that of all previously recorded errors. Of
+    /// several errors at the same position, the first one is kept.
+    pub fn record(&mut self, err: Error<E>) {
+        match &self.furthest {
+            Some(furthest) if furthest.position >= err.position => {}
+            _ => self.furthest = Some(err),
+        }
+    }
+
+    /// The furthest error that has been recorded, if any.
+    pub fn furthest(&self) -> Option<&Error<E>> {
+        self.furthest.as_ref()
+    }
+
+    /// Remove and return the furthest error that has been recorded, if any.
+    pub fn take_furthest(&mut self) -> Option<Error<E>> {
+        self.furthest.take()
+    }
+}
+
+impl<E> Default for FurthestError<E> {
+    fn default() -> Self {
+        FurthestError::new()
+    }
+}
+
+/// Collects errors, so that a parser can report all errors of an input rather than stopping at
+/// the first one. See `ParserHelper::recovering`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ErrorAccumulator<E> {
+    errors: Vec<Error<E>>,
+}
+
+impl<E> ErrorAccumulator<E> {
+    /// Create an accumulator without any errors.
+    pub fn new() -> Self {
+        ErrorAccumulator { errors: Vec::new() }
+    }
+
+    /// Add an error.
+    pub fn record(&mut self, err: Error<E>) {
+        self.errors.push(err);
+    }
+
+    /// All errors recorded so far, in the order in which they were recorded.
+    pub fn errors(&self) -> &[Error<E>] {
+        &self.errors
+    }
+
+    /// Whether no errors have been recorded.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Obtain the recorded errors.
+    pub fn into_errors(self) -> Vec<Error<E>> {
+        self.errors
+    }
+}
+
+impl<E> Default for ErrorAccumulator<E> {
+    fn default() -> Self {
+        ErrorAccumulator::new()
+    }
+}
+
+/// A position in the input, given as a line and a column, both starting at one. Columns count
+/// bytes, not characters, and lines are terminated by `\n` (so `\r\n` is a single line break).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct LineColPosition {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for LineColPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// Compute the line and column of the byte at `offset` in `input`, by scanning for newlines. An
+/// `offset` beyond the input is treated as the end of the input.
+pub fn position_to_line_col(input: &[u8], offset: usize) -> LineColPosition {
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    LineColPosition {
+        line: before.iter().filter(|b| **b == b'\n').count() + 1,
+        col: before.len() - line_start + 1,
+    }
+}
+
+/// The positions of all line breaks in an input, for looking up line and column numbers (same
+/// conventions as `LineColPosition`) without having to rescan the input for every lookup.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct LineIndex {
+    newlines: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    /// Scan `input` for line breaks.
+    pub fn new(input: &[u8]) -> Self {
+        let newlines = input
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .map(|(i, _)| i)
+            .collect();
+        LineIndex {
+            newlines,
+            len: input.len(),
+        }
+    }
+
+    /// The line and column of the byte at `offset`. An `offset` beyond the input is treated as the
+    /// end of the input.
+    ///
+    /// ```
+    /// use atm_parser_helper::LineIndex;
+    ///
+    /// let index = LineIndex::new(b"ab\ncd");
+    /// assert_eq!(index.location(0), (1, 1));
+    /// assert_eq!(index.location(3), (2, 1));
+    /// assert_eq!(index.location(5), (2, 3));
+    /// assert_eq!(index.location(9), (2, 3));
+    /// ```
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.len);
+        let line = self.newlines.partition_point(|newline| *newline < offset);
+        let line_start = if line == 0 { 0 } else { self.newlines[line - 1] + 1 };
+        (line + 1, offset - line_start + 1)
+    }
+}
+
+/// A parse error, tagging an arbitrary error type with both a byte offset and the corresponding
+/// line and column. Obtained via `Error::to_located`.
+#[derive(Error, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[error("parse error at {line_col} (position {position}): {e}")]
+pub struct LocatedError<E> {
+    pub position: usize,
+    pub line_col: LineColPosition,
+    pub e: E,
+}
+
+/// A minimal error type implementing `Eoi`, for prototypes and tests.
+#[derive(Error, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum MiniError {
+    /// The input ended unexpectedly.
+    #[error("unexpected end of input")]
+    Eoi,
+    /// Any other error, described by a message.
+    #[error("{0}")]
+    Custom(&'static str),
+}
+
+impl MiniError {
+    /// Create an error described by the given message.
+    pub fn msg(msg: &'static str) -> Self {
+        MiniError::Custom(msg)
+    }
+}
+
+impl Eoi for MiniError {
+    fn eoi() -> Self {
+        MiniError::Eoi
+    }
+}
+
+impl NoProgress for MiniError {
+    fn no_progress() -> Self {
+        MiniError::Custom("repeated parser consumed no input")
+    }
+}
+
+impl IsEoi for MiniError {
+    fn is_eoi(&self) -> bool {
+        matches!(self, MiniError::Eoi)
+    }
+}
+
+impl From<&'static str> for MiniError {
+    fn from(msg: &'static str) -> Self {
+        MiniError::msg(msg)
+    }
+}
+
+/// Adds an end of input variant to an arbitrary error type, so that it can be used with methods
+/// that require `Eoi`.
+///
+/// ```
+/// use std::num::ParseIntError;
+/// use atm_parser_helper::{Error, OrEoi, ParserHelper};
+///
+/// fn parse_u8(p: &mut ParserHelper) -> Result<u8, Error<OrEoi<ParseIntError>>> {
+///     let start = p.position();
+///     p.peek::<OrEoi<ParseIntError>>()?;
+///     let digits = std::str::from_utf8(p.take_ascii_digits()).unwrap();
+///     digits.parse().or_else(|e| p.fail_at_position(OrEoi::Other(e), start))
+/// }
+///
+/// assert_eq!(parse_u8(&mut ParserHelper::new(b"42")), Ok(42));
+/// assert!(parse_u8(&mut ParserHelper::new(b"300")).unwrap_err().into_other().is_ok());
+/// assert!(parse_u8(&mut ParserHelper::new(b"")).unwrap_err().into_other().is_err());
+/// ```
+#[derive(Error, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum OrEoi<E> {
+    /// The input ended unexpectedly.
+    #[error("unexpected end of input")]
+    Eoi,
+    /// The wrapped error.
+    #[error(transparent)]
+    Other(E),
+}
+
+impl<E> Eoi for OrEoi<E> {
+    fn eoi() -> Self {
+        OrEoi::Eoi
+    }
+}
+
+impl<E> IsEoi for OrEoi<E> {
+    fn is_eoi(&self) -> bool {
+        matches!(self, OrEoi::Eoi)
+    }
+}
+
+impl<E> From<E> for OrEoi<E> {
+    fn from(e: E) -> Self {
+        OrEoi::Other(e)
+    }
+}
+
+impl<E> Error<OrEoi<E>> {
+    /// Returns the wrapped error at the same position, or gives back `self` if this is an
+    /// unexpected end of input.
+    pub fn into_other(self) -> Result<Error<E>, Self> {
+        match self.e {
+            OrEoi::Other(e) => Ok(Error::new(self.position, e)),
+            OrEoi::Eoi => Err(self),
+        }
+    }
+}
+
 impl<'a> ParserHelper<'a> {
     /// Parses from a slice of bytes.
     pub fn new(input: &'a [u8]) -> Self {
@@ -56,21 +737,100 @@ impl<'a> ParserHelper<'a> {
         self.input.len()
     }
 
+    /// Return the number of bytes that have not been consumed yet.
+    pub fn remaining_len(&self) -> usize {
+        self.len().saturating_sub(self.position())
+    }
+
+    /// Return whether all input has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.remaining_len() == 0
+    }
+
+    /// Return whether there is input left that has not been consumed yet.
+    pub fn has_remaining(&self) -> bool {
+        !self.is_empty()
+    }
+
     /// Obtain a slice into the original input.
     pub fn slice<I: SliceIndex<[u8]>>(&self, i: I) -> &'a I::Output {
         &self.input[i]
     }
 
+    /// Obtain the byte at the given absolute position, or `None` if it lies beyond the input.
+    pub fn byte_at(&self, index: usize) -> Option<u8> {
+        self.input.get(index).copied()
+    }
+
+    /// Obtain the slice of input between the two given positions. In debug builds, panics if
+    /// `start > end` or `end > self.len()`, otherwise clamps the positions to the input.
+    pub fn slice_between(&self, start: usize, end: usize) -> &'a [u8] {
+        debug_assert!(start <= end, "slice start {} is after its end {}", start, end);
+        debug_assert!(end <= self.len(), "slice end {} is beyond the end of the input", end);
+        let end = end.min(self.len());
+        self.slice(start.min(end)..end)
+    }
+
+    /// Obtain the slice of input from `start` up to the current position. In debug builds, panics
+    /// if `start` lies after the current position, otherwise clamps the positions to the input.
+    pub fn slice_since(&self, start: usize) -> &'a [u8] {
+        debug_assert!(
+            start <= self.position(),
+            "slice start {} is after the current position {}",
+            start,
+            self.position(),
+        );
+        let end = self.position().min(self.len());
+        self.slice(start.min(end)..end)
+    }
+
     /// Reference to portion of buffer yet to be parsed
     pub fn rest(&self) -> &'a [u8] {
         self.slice(self.position()..)
     }
 
+    /// Reference to portion of buffer already parsed, i.e., everything before the current
+    /// position. Clamped to the input if the position lies beyond its end.
+    pub fn consumed(&self) -> &'a [u8] {
+        self.slice(..self.position().min(self.len()))
+    }
+
     /// Current byte offset of buffer being parsed
     pub fn position(&self) -> usize {
         self.position
     }
 
+    /// Save the current position, so that it can later be restored via `restore`.
+    pub fn mark(&self) -> Mark<'a> {
+        Mark {
+            position: self.position(),
+            input: PhantomData,
+            #[cfg(debug_assertions)]
+            input_address: self.input.as_ptr() as usize,
+        }
+    }
+
+    /// Move to a previously saved position. This can also move forward, if the mark was created
+    /// after the current position. In debug builds, panics if the mark was created for a
+    /// different input or lies beyond the end of the input.
+    pub fn restore(&mut self, mark: Mark<'a>) {
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            mark.input_address,
+            self.input.as_ptr() as usize,
+            "mark was created for a different input"
+        );
+        debug_assert!(mark.position <= self.len(), "mark lies beyond the end of the input");
+        self.position = mark.position;
+    }
+
+    /// Set the current position to an arbitrary absolute offset. In debug builds, panics if the
+    /// offset lies beyond the end of the input.
+    pub fn reset_position(&mut self, pos: usize) {
+        debug_assert!(pos <= self.len(), "position {} is beyond the end of the input", pos);
+        self.position = pos;
+    }
+
     /// Produce an error at the current position.
     pub fn fail<T, E>(&self, reason: E) -> Result<T, Error<E>> {
         self.fail_at_position(reason, self.position())
@@ -81,46 +841,192 @@ impl<'a> ParserHelper<'a> {
         Err(Error::new(position, reason))
     }
 
+    /// Produce an error spanning from `start` to `end`.
+    pub fn fail_spanned<T, E>(
+        &self,
+        reason: E,
+        start: usize,
+        end: usize,
+    ) -> Result<T, ErrorSpan<E>> {
+        Err(ErrorSpan {
+            start,
+            end,
+            e: reason,
+        })
+    }
+
+    /// Produce an error spanning from `start` to the current position.
+    pub fn fail_spanning<T, E>(&self, start: usize, e: E) -> Result<T, ErrorSpan<E>> {
+        Err(ErrorSpan {
+            start,
+            end: self.position(),
+            e,
+        })
+    }
+
     /// Produce an error indicating the unexpected end of the input at the current position.
     pub fn unexpected_end_of_input<T, E: Eoi>(&self) -> Result<T, Error<E>> {
         self.fail(E::eoi())
     }
 
+    /// Produce an error indicating the unexpected end of the input at the current position, where
+    /// `needed` more bytes of input would have been required.
+    pub fn unexpected_end_of_input_needing<T, E: Eoi>(&self, needed: usize) -> Result<T, Error<E>> {
+        self.fail(E::eoi_needing(needed))
+    }
+
     /// Advance the input slice by some number of bytes.
     pub fn advance(&mut self, offset: usize) {
         self.position += offset;
     }
 
+    /// Move the input slice back by some number of bytes, but not before the start of the input.
+    pub fn advance_back(&mut self, offset: usize) {
+        debug_assert!(offset <= self.position, "cannot move back before the start of the input");
+        self.position = self.position.saturating_sub(offset);
+    }
+
     /// Advance the input but only if it matches the given bytes, returns whether it did advance.
     pub fn advance_over(&mut self, expected: &[u8]) -> bool {
         if self.rest().starts_with(expected) {
             self.advance(expected.len());
-            return true;
+            true
         } else {
-            return false;
+            false
         }
     }
 
     /// Advance the input slice by some number of bytes, returning the given error if not enough
     /// input is available.
+    /// Does not advance at all if not enough input is available.
     pub fn advance_or<E>(&mut self, offset: usize, e: E) -> Result<(), Error<E>> {
-        let start = self.position;
-        self.position += offset;
-        if self.len() < self.position {
-            return self.fail_at_position(e, start);
-        } else {
-            return Ok(());
+        self.advance_checked(offset, e)
+    }
+
+    /// Advance the input slice by some number of bytes, returning the given error at the current
+    /// position (without advancing) if not enough input is available.
+    pub fn advance_checked<E>(&mut self, offset: usize, e: E) -> Result<(), Error<E>> {
+        match self.position().checked_add(offset) {
+            Some(end) if end <= self.len() => {
+                self.position = end;
+                Ok(())
+            }
+            _ => self.fail(e),
         }
     }
 
-    /// Consumes the next byte and returns it.
-    /// Signals unexpected end of the input if no next byte is available.
-    pub fn next<E: Eoi>(&mut self) -> Result<u8, Error<E>> {
-        if let Some(c) = self.input.get(self.position()) {
-            self.advance(1);
-            Ok(*c)
+    /// Advance the input slice to the given absolute position, returning the given error at the
+    /// current position (without advancing) if that would mean moving backwards or beyond the end
+    /// of the input.
+    pub fn skip_to<E>(&mut self, absolute_position: usize, e: E) -> Result<(), Error<E>> {
+        if absolute_position < self.position() || absolute_position > self.len() {
+            self.fail(e)
         } else {
-            self.unexpected_end_of_input()
+            self.position = absolute_position;
+            Ok(())
+        }
+    }
+
+    /// Skips the next `n` bytes.
+    /// Signals unexpected end of the input without skipping anything if fewer than `n` bytes are
+    /// available.
+    pub fn skip_n<E: Eoi>(&mut self, n: usize) -> Result<(), Error<E>> {
+        self.take(n).map(|_| ())
+    }
+
+    /// Consumes the next `n` bytes and returns them, returning the given error without consuming
+    /// anything if fewer than `n` bytes are available.
+    pub fn take_n<E>(&mut self, n: usize, e: E) -> Result<&'a [u8], Error<E>> {
+        if let Some(taken) = self.rest().get(..n) {
+            self.advance(n);
+            Ok(taken)
+        } else {
+            self.fail(e)
+        }
+    }
+
+    /// Consumes the next `n` bytes and returns them.
+    /// Signals unexpected end of the input without consuming anything if fewer than `n` bytes are
+    /// available.
+    pub fn take<E: Eoi>(&mut self, n: usize) -> Result<&'a [u8], Error<E>> {
+        let rest = self.rest();
+        if let Some(taken) = rest.get(..n) {
+            self.advance(n);
+            Ok(taken)
+        } else {
+            self.unexpected_end_of_input_needing(n - rest.len())
+        }
+    }
+
+    /// Consumes the next `N` bytes and returns them.
+    /// Signals unexpected end of the input without consuming anything if fewer than `N` bytes are
+    /// available.
+    pub fn next_array<const N: usize, E: Eoi>(&mut self) -> Result<[u8; N], Error<E>> {
+        self.next_array_ref().copied()
+    }
+
+    /// Same as `next_array`, but returns a reference into the input instead of a copy.
+    pub fn next_array_ref<const N: usize, E: Eoi>(&mut self) -> Result<&'a [u8; N], Error<E>> {
+        let rest = self.rest();
+        if let Some(taken) = rest.first_chunk() {
+            self.advance(N);
+            Ok(taken)
+        } else {
+            self.unexpected_end_of_input_needing(N - rest.len())
+        }
+    }
+
+    /// Consumes the next 2 bytes and decodes them as a big-endian `u16`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 2 bytes are
+    /// available.
+    pub fn read_u16_be<E: Eoi>(&mut self) -> Result<u16, Error<E>> {
+        self.next_array().map(u16::from_be_bytes)
+    }
+
+    /// Consumes the next 4 bytes and decodes them as a big-endian `u32`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 4 bytes are
+    /// available.
+    pub fn read_u32_be<E: Eoi>(&mut self) -> Result<u32, Error<E>> {
+        self.next_array().map(u32::from_be_bytes)
+    }
+
+    /// Consumes the next 8 bytes and decodes them as a big-endian `u64`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 8 bytes are
+    /// available.
+    pub fn read_u64_be<E: Eoi>(&mut self) -> Result<u64, Error<E>> {
+        self.next_array().map(u64::from_be_bytes)
+    }
+
+    /// Consumes the next 2 bytes and decodes them as a little-endian `u16`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 2 bytes are
+    /// available.
+    pub fn read_u16_le<E: Eoi>(&mut self) -> Result<u16, Error<E>> {
+        self.next_array().map(u16::from_le_bytes)
+    }
+
+    /// Consumes the next 4 bytes and decodes them as a little-endian `u32`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 4 bytes are
+    /// available.
+    pub fn read_u32_le<E: Eoi>(&mut self) -> Result<u32, Error<E>> {
+        self.next_array().map(u32::from_le_bytes)
+    }
+
+    /// Consumes the next 8 bytes and decodes them as a little-endian `u64`.
+    /// Signals unexpected end of the input without consuming anything if fewer than 8 bytes are
+    /// available.
+    pub fn read_u64_le<E: Eoi>(&mut self) -> Result<u64, Error<E>> {
+        self.next_array().map(u64::from_le_bytes)
+    }
+
+    /// Consumes the next byte and returns it.
+    /// Signals unexpected end of the input if no next byte is available.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<E: Eoi>(&mut self) -> Result<u8, Error<E>> {
+        if let Some(c) = self.input.get(self.position()) {
+            self.advance(1);
+            Ok(*c)
+        } else {
+            self.unexpected_end_of_input_needing(1)
         }
     }
 
@@ -134,6 +1040,19 @@ impl<'a> ParserHelper<'a> {
         }
     }
 
+    /// Consumes the next byte and returns it if it satisfies the predicate, returns `None`
+    /// without consuming anything otherwise (or at the end of the input).
+    pub fn next_if(&mut self, pred: impl FnOnce(u8) -> bool) -> Option<u8> {
+        let c = self.peek_or_end().filter(|c| pred(*c))?;
+        self.advance(1);
+        Some(c)
+    }
+
+    /// Consumes the next byte if it is the expected one, returns whether it did consume.
+    pub fn next_if_eq(&mut self, expected: u8) -> bool {
+        self.next_if(|c| c == expected).is_some()
+    }
+
     /// Consumes the expected byte, gives the given error if it is something else.
     pub fn expect<E: Eoi>(&mut self, expected: u8, err: E) -> Result<(), Error<E>> {
         let pos = self.position();
@@ -154,8 +1073,93 @@ impl<'a> ParserHelper<'a> {
         }
     }
 
+    /// Same as `advance_over`, but compares ASCII letters case-insensitively.
+    pub fn advance_over_no_case(&mut self, expected: &[u8]) -> bool {
+        match self.rest().get(..expected.len()) {
+            Some(actual) if actual.eq_ignore_ascii_case(expected) => {
+                self.advance(expected.len());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Same as `expect_bytes`, but compares ASCII letters case-insensitively. The error points at
+    /// the first byte that does not match, or at the end of the input if it is too short.
+    pub fn expect_bytes_no_case<E>(&mut self, expected: &[u8], err: E) -> Result<(), Error<E>> {
+        let rest = self.rest();
+        match rest.iter().zip(expected).position(|(a, b)| !a.eq_ignore_ascii_case(b)) {
+            Some(offset) => self.fail_at_position(err, self.position() + offset),
+            None if rest.len() < expected.len() => self.fail_at_position(err, self.len()),
+            None => {
+                self.advance(expected.len());
+                Ok(())
+            }
+        }
+    }
+
+    /// Same as `expect`, but creates the error from the byte that was found instead, and does not
+    /// consume that byte.
+    pub fn expect_found<E: Eoi + Unexpected>(&mut self, expected: u8) -> Result<(), Error<E>> {
+        self.expect_pred_found(|c| c == expected)
+    }
+
+    /// Same as `expect_pred`, but creates the error from the byte that was found instead, and does
+    /// not consume that byte.
+    pub fn expect_pred_found<E: Eoi + Unexpected>(
+        &mut self,
+        pred: impl FnOnce(u8) -> bool,
+    ) -> Result<(), Error<E>> {
+        let found = self.peek()?;
+        if pred(found) {
+            self.advance(1);
+            Ok(())
+        } else {
+            self.fail(E::unexpected(found))
+        }
+    }
+
+    /// Consumes the next byte and returns it unless it is `forbidden`, in which case gives the
+    /// given error without consuming anything.
+    /// Signals unexpected end of the input if no next byte is available.
+    pub fn expect_not<E: Eoi>(&mut self, forbidden: u8, err: E) -> Result<u8, Error<E>> {
+        self.expect_not_pred(|c| c == forbidden, err)
+    }
+
+    /// Consumes the next byte and returns it unless it satisfies the predicate, in which case
+    /// gives the given error without consuming anything.
+    /// Signals unexpected end of the input if no next byte is available.
+    pub fn expect_not_pred<E: Eoi>(
+        &mut self,
+        pred: impl FnOnce(u8) -> bool,
+        err: E,
+    ) -> Result<u8, Error<E>> {
+        if pred(self.peek()?) {
+            self.fail(err)
+        } else {
+            self.next()
+        }
+    }
+
+    /// Advances over the first of the `candidates` that the remaining input starts with, and
+    /// returns its index. Gives the given error if none of them match.
+    pub fn expect_one_of_sequences<E>(
+        &mut self,
+        candidates: &[&[u8]],
+        err: E,
+    ) -> Result<usize, Error<E>> {
+        match candidates.iter().position(|candidate| self.advance_over(candidate)) {
+            Some(i) => Ok(i),
+            None => self.fail(err),
+        }
+    }
+
     /// Same as expect, but using a predicate.
-    pub fn expect_pred<E: Eoi>(&mut self, pred: fn(u8) -> bool, err: E) -> Result<(), Error<E>> {
+    pub fn expect_pred<E: Eoi>(
+        &mut self,
+        pred: impl FnOnce(u8) -> bool,
+        err: E,
+    ) -> Result<(), Error<E>> {
         let pos = self.position();
         if pred(self.next()?) {
             Ok(())
@@ -164,13 +1168,52 @@ impl<'a> ParserHelper<'a> {
         }
     }
 
+    /// Consumes the next byte and returns it if it is contained in `set`, gives the given error
+    /// without consuming anything if it is not.
+    /// Signals unexpected end of the input if no next byte is available.
+    pub fn one_of<E: Eoi>(&mut self, set: &[u8], err: E) -> Result<u8, Error<E>> {
+        if set.contains(&self.peek()?) {
+            self.next()
+        } else {
+            self.fail(err)
+        }
+    }
+
+    /// Returns the next byte without consuming it if it is contained in `set`, `None` otherwise.
+    pub fn peek_one_of(&self, set: &[u8]) -> Option<u8> {
+        self.peek_or_end().filter(|c| set.contains(c))
+    }
+
+    /// Consumes the next byte and returns it if it is not contained in `set`, gives the given
+    /// error without consuming anything if it is.
+    /// Signals unexpected end of the input if no next byte is available.
+    pub fn none_of<E: Eoi>(&mut self, set: &[u8], err: E) -> Result<u8, Error<E>> {
+        if set.contains(&self.peek()?) {
+            self.fail(err)
+        } else {
+            self.next()
+        }
+    }
+
+    /// Consumes the next byte and returns it if it lies in the inclusive range from `lo` to `hi`,
+    /// gives the given error without consuming anything if it does not. Always fails if
+    /// `lo > hi`.
+    /// Signals unexpected end of the input if no next byte is available.
+    pub fn expect_byte_range<E: Eoi>(&mut self, lo: u8, hi: u8, err: E) -> Result<u8, Error<E>> {
+        if (lo..=hi).contains(&self.peek()?) {
+            self.next()
+        } else {
+            self.fail(err)
+        }
+    }
+
     /// Returns the next byte without consuming it.
     /// Signals unexpected end of the input if no next byte is available.
     pub fn peek<E: Eoi>(&self) -> Result<u8, Error<E>> {
         if let Some(c) = self.input.get(self.position()) {
             Ok(*c)
         } else {
-            self.unexpected_end_of_input()
+            self.unexpected_end_of_input_needing(1)
         }
     }
 
@@ -179,19 +1222,2392 @@ impl<'a> ParserHelper<'a> {
         self.input.get(self.position()).copied()
     }
 
-    /// Skips values while the predicate returns true.
-    pub fn skip(&mut self, pred: fn(u8) -> bool) {
+    /// Returns the byte `offset` positions after the next byte without consuming anything, so
+    /// `peek_ahead(0)` is the same as `peek()`.
+    /// Signals unexpected end of the input (at the current position) if the input is too short.
+    pub fn peek_ahead<E: Eoi>(&self, offset: usize) -> Result<u8, Error<E>> {
+        if let Some(c) = self.peek_ahead_or_end(offset) {
+            Ok(c)
+        } else {
+            self.unexpected_end_of_input_needing(offset.saturating_add(1) - self.remaining_len())
+        }
+    }
+
+    /// Returns the byte `offset` positions after the next byte without consuming anything, or
+    /// signals end of input as `None`.
+    pub fn peek_ahead_or_end(&self, offset: usize) -> Option<u8> {
+        let i = self.position().checked_add(offset)?;
+        self.input.get(i).copied()
+    }
+
+    /// Returns the next `n` bytes without consuming them.
+    /// Signals unexpected end of the input if fewer than `n` bytes are available.
+    pub fn peek_slice<E: Eoi>(&self, n: usize) -> Result<&'a [u8], Error<E>> {
+        let rest = self.rest();
+        if let Some(peeked) = rest.get(..n) {
+            Ok(peeked)
+        } else {
+            self.unexpected_end_of_input_needing(n - rest.len())
+        }
+    }
+
+    /// Returns the next `N` bytes without consuming them.
+    /// Signals unexpected end of the input if fewer than `N` bytes are available.
+    pub fn peek_array<const N: usize, E: Eoi>(&self) -> Result<[u8; N], Error<E>> {
+        let mut peeked = [0; N];
+        peeked.copy_from_slice(self.peek_slice(N)?);
+        Ok(peeked)
+    }
+
+    /// Returns the next `n` bytes without consuming them, or fewer if the input ends before.
+    pub fn peek_slice_or_end(&self, n: usize) -> &'a [u8] {
+        let rest = self.rest();
+        &rest[..n.min(rest.len())]
+    }
+
+    /// Returns whether the remaining input starts with the given bytes, without consuming
+    /// anything.
+    pub fn rest_starts_with(&self, tag: &[u8]) -> bool {
+        let end = match self.position().checked_add(tag.len()) {
+            Some(end) => end,
+            None => return false,
+        };
+        self.input.get(self.position()..end) == Some(tag)
+    }
+
+    /// Returns whether the next byte satisfies the predicate, without consuming it. Returns
+    /// false at the end of the input.
+    pub fn peek_matches(&self, pred: impl FnOnce(u8) -> bool) -> bool {
+        self.peek_or_end().is_some_and(pred)
+    }
+
+    /// Skips values while the predicate returns true, and returns how many values were skipped.
+    pub fn skip(&mut self, mut pred: impl FnMut(u8) -> bool) -> usize {
+        let start = self.position();
         loop {
             match self.peek_or_end() {
-                None => return,
-                Some(peeked) => {
-                    if pred(peeked) {
-                        self.advance(1);
-                    } else {
-                        return;
+                Some(peeked) if pred(peeked) => self.advance(1),
+                _ => return self.position() - start,
+            }
+        }
+    }
+
+    /// Same as `skip`.
+    pub fn advance_while(&mut self, pred: impl FnMut(u8) -> bool) -> usize {
+        self.skip(pred)
+    }
+
+    /// Skips at most `max` values while the predicate returns true, and returns how many values
+    /// were skipped.
+    pub fn skip_max(&mut self, pred: impl FnMut(u8) -> bool, max: usize) -> usize {
+        self.take_while_bounded(max, pred).len()
+    }
+
+    /// Skips values while the predicate returns true, and returns how many values were skipped.
+    /// If fewer than `min` values match, returns the given error at the position where skipping
+    /// stopped, without skipping anything.
+    pub fn skip_at_least<E>(
+        &mut self,
+        pred: impl FnMut(u8) -> bool,
+        min: usize,
+        err: E,
+    ) -> Result<usize, Error<E>> {
+        Ok(self.take_while_m_n(min, usize::MAX, pred, err)?.len())
+    }
+
+    /// Consumes values while the predicate returns true, and returns the consumed slice.
+    /// Afterwards, the position points at the first value that did not satisfy the predicate (or
+    /// at the end of the input).
+    pub fn take_while(&mut self, pred: impl FnMut(u8) -> bool) -> &'a [u8] {
+        let start = self.position();
+        self.skip(pred);
+        self.slice(start..self.position())
+    }
+
+    /// Same as `take_while`, but returns the given error without consuming anything if not a
+    /// single value satisfies the predicate. This includes the case where the input is exhausted.
+    pub fn take_while1<E>(
+        &mut self,
+        pred: impl FnMut(u8) -> bool,
+        err: E,
+    ) -> Result<&'a [u8], Error<E>> {
+        let taken = self.take_while(pred);
+        if taken.is_empty() {
+            self.fail(err)
+        } else {
+            Ok(taken)
+        }
+    }
+
+    /// Consumes at most `max` values while the predicate returns true, and returns the consumed
+    /// slice. If fewer than `min` values match, returns the given error at the position where
+    /// matching stopped, without consuming anything.
+    pub fn take_while_m_n<E>(
+        &mut self,
+        min: usize,
+        max: usize,
+        pred: impl FnMut(u8) -> bool,
+        err: E,
+    ) -> Result<&'a [u8], Error<E>> {
+        let taken = self.take_while_bounded(max, pred);
+        if taken.len() < min {
+            let stopped = self.position();
+            self.position -= taken.len();
+            self.fail_at_position(err, stopped)
+        } else {
+            Ok(taken)
+        }
+    }
+
+    /// Consumes at most `max` values while the predicate returns true, and returns the consumed
+    /// slice.
+    pub fn take_while_bounded(
+        &mut self,
+        max: usize,
+        mut pred: impl FnMut(u8) -> bool,
+    ) -> &'a [u8] {
+        let start = self.position();
+        while self.position() - start < max {
+            match self.peek_or_end() {
+                Some(peeked) if pred(peeked) => self.advance(1),
+                _ => break,
+            }
+        }
+        self.slice(start..self.position())
+    }
+
+    /// Consumes values up to (but not including) the first occurrence of `delimiter`, and returns
+    /// the consumed slice. Consumes all remaining input if `delimiter` does not occur.
+    pub fn take_until_byte(&mut self, delimiter: u8) -> &'a [u8] {
+        let rest = self.rest();
+        let len = find_byte(delimiter, rest).unwrap_or(rest.len());
+        self.advance(len);
+        &rest[..len]
+    }
+
+    /// Same as `take_until_byte`, but returns the given error without consuming anything if
+    /// `delimiter` does not occur.
+    pub fn take_until_byte_or<E>(&mut self, delimiter: u8, err: E) -> Result<&'a [u8], Error<E>> {
+        let rest = self.rest();
+        match find_byte(delimiter, rest) {
+            Some(len) => {
+                self.advance(len);
+                Ok(&rest[..len])
+            }
+            None => self.fail(err),
+        }
+    }
+
+    /// Consumes values up to (but not including) the first occurrence of `pattern`, and returns
+    /// the consumed slice. Consumes all remaining input if `pattern` does not occur.
+    pub fn take_until_pattern(&mut self, pattern: &[u8]) -> &'a [u8] {
+        let rest = self.rest();
+        let len = find_slice(pattern, rest).unwrap_or(rest.len());
+        self.advance(len);
+        &rest[..len]
+    }
+
+    /// Consumes all remaining input and returns it. Returns an empty slice if the input has
+    /// already been exhausted, and clamps the position to the end of the input if it lies beyond.
+    pub fn take_remaining(&mut self) -> &'a [u8] {
+        let rest = self.slice(self.position().min(self.len())..);
+        self.position = self.len();
+        rest
+    }
+
+    /// Consumes values up to (but not including) the first occurrence of `delimiter`, and returns
+    /// the consumed slice. Returns the given error without consuming anything if `delimiter`
+    /// does not occur.
+    pub fn take_until_slice<E>(&mut self, delimiter: &[u8], err: E) -> Result<&'a [u8], Error<E>> {
+        let rest = self.rest();
+        match find_slice(delimiter, rest) {
+            Some(len) => {
+                self.advance(len);
+                Ok(&rest[..len])
+            }
+            None => self.fail(err),
+        }
+    }
+
+    /// Same as `take_until_slice`, but also consumes the `delimiter` (which is not part of the
+    /// returned slice).
+    pub fn take_through_slice<E>(
+        &mut self,
+        delimiter: &[u8],
+        err: E,
+    ) -> Result<&'a [u8], Error<E>> {
+        let taken = self.take_until_slice(delimiter, err)?;
+        self.advance(delimiter.len());
+        Ok(taken)
+    }
+
+    /// Consumes values while the predicate returns true, then consumes the first value that does
+    /// not satisfy the predicate, and returns the consumed slice (including that final value).
+    /// Signals unexpected end of the input (at the end of the input) without consuming anything if
+    /// the input ends before a value fails the predicate.
+    pub fn take_while_inclusive<E: Eoi>(
+        &mut self,
+        pred: impl FnMut(u8) -> bool,
+    ) -> Result<&'a [u8], Error<E>> {
+        let start = self.position();
+        self.skip(pred);
+        if self.position() < self.len() {
+            self.advance(1);
+            Ok(self.slice(start..self.position()))
+        } else {
+            let end = self.position();
+            self.position = start;
+            self.fail_at_position(E::eoi(), end)
+        }
+    }
+
+    /// Consumes a (possibly empty) run of ASCII decimal digits and returns it.
+    pub fn take_ascii_digits(&mut self) -> &'a [u8] {
+        self.take_while(|c| c.is_ascii_digit())
+    }
+
+    /// Same as `take_ascii_digits`, but returns the given error if there is not a single digit.
+    pub fn take_ascii_digits_nonempty<E>(&mut self, err: E) -> Result<&'a [u8], Error<E>> {
+        self.take_while1(|c| c.is_ascii_digit(), err)
+    }
+
+    /// Consumes a (possibly empty) run of ASCII hexadecimal digits (either case) and returns it.
+    pub fn take_ascii_hex_digits(&mut self) -> &'a [u8] {
+        self.take_while(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Same as `take_ascii_hex_digits`, but returns the given error if there is not a single
+    /// digit.
+    pub fn take_ascii_hex_digits_nonempty<E>(&mut self, err: E) -> Result<&'a [u8], Error<E>> {
+        self.take_while1(|c| c.is_ascii_hexdigit(), err)
+    }
+
+    /// Skips values up to (but not including) the first occurrence of `stop`. Skips all remaining
+    /// input if `stop` does not occur.
+    pub fn skip_until_byte(&mut self, stop: u8) {
+        self.take_until_byte(stop);
+    }
+
+    /// Consumes values while they are not contained in `set`, and returns the consumed slice.
+    pub fn take_while_none_of(&mut self, set: &[u8]) -> &'a [u8] {
+        let set = ByteSet::new(set);
+        self.take_while(|c| !set.contains(c))
+    }
+
+    /// Skips values up to (but not including) the first occurrence of `pattern`. Skips all
+    /// remaining input if `pattern` does not occur.
+    pub fn skip_until_pattern(&mut self, pattern: &[u8]) {
+        self.take_until_pattern(pattern);
+    }
+
+    /// Consumes values while they lie in the inclusive range from `lo` to `hi`, and returns the
+    /// consumed slice.
+    pub fn take_while_in_range(&mut self, lo: u8, hi: u8) -> &'a [u8] {
+        self.take_while(|c| (lo..=hi).contains(&c))
+    }
+
+    /// Gives the given error (at the position of the first unconsumed byte) unless all input has
+    /// been consumed.
+    pub fn expect_end_of_input<E>(&self, err: E) -> Result<(), Error<E>> {
+        if self.position() >= self.len() {
+            Ok(())
+        } else {
+            self.fail(err)
+        }
+    }
+
+    /// Consumes exactly `n` values that all satisfy the predicate, and returns them. Without
+    /// consuming anything, gives the given error at the first value that does not satisfy the
+    /// predicate, or signals unexpected end of the input if the input ends before `n` values.
+    pub fn take_exact_pred<E: Eoi>(
+        &mut self,
+        n: usize,
+        pred: impl FnMut(u8) -> bool,
+        err: E,
+    ) -> Result<&'a [u8], Error<E>> {
+        let taken = self.take_while_bounded(n, pred);
+        if taken.len() == n {
+            return Ok(taken);
+        }
+
+        let stopped = self.position();
+        self.position -= taken.len();
+        if stopped < self.len() {
+            self.fail_at_position(err, stopped)
+        } else {
+            self.fail_at_position(E::eoi_needing(n - taken.len()), stopped)
+        }
+    }
+
+    /// Same as `take_exact_pred`, but discards the consumed values.
+    pub fn skip_exact<E: Eoi>(
+        &mut self,
+        n: usize,
+        pred: impl FnMut(u8) -> bool,
+        err: E,
+    ) -> Result<(), Error<E>> {
+        self.take_exact_pred(n, pred, err).map(|_| ())
+    }
+
+    /// Start a transaction: the returned guard can be used like the parser helper itself, and
+    /// restores the current position when dropped, unless `Transaction::commit` is called. This
+    /// ensures that errors propagated via `?` do not leave the helper in the middle of a token.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError, ParserHelper};
+    ///
+    /// fn keyword(p: &mut ParserHelper, kw: &[u8]) -> Result<(), Error<MiniError>> {
+    ///     let mut tx = p.transaction();
+    ///     tx.expect_bytes(kw, MiniError::msg("expected keyword"))?;
+    ///     tx.expect(b' ', MiniError::msg("expected a space"))?;
+    ///     tx.commit();
+    ///     Ok(())
+    /// }
+    ///
+    /// let mut p = ParserHelper::new(b"letx");
+    /// assert!(keyword(&mut p, b"let").is_err());
+    /// assert_eq!(p.position(), 0);
+    ///
+    /// // Transactions nest, a rolled back inner transaction keeps the progress of the outer one.
+    /// let mut p = ParserHelper::new(b"ab");
+    /// let mut outer = p.transaction();
+    /// outer.advance(1);
+    /// {
+    ///     let mut inner = outer.transaction();
+    ///     inner.advance(1);
+    /// }
+    /// assert_eq!(outer.position(), 1);
+    /// outer.commit();
+    /// assert_eq!(p.position(), 1);
+    /// ```
+    pub fn transaction(&mut self) -> Transaction<'_, 'a> {
+        Transaction {
+            start: self.mark(),
+            p: self,
+            committed: false,
+        }
+    }
+
+    /// Run a sub-parser, and restore the prior position if it fails. The error is returned
+    /// unchanged, so its position still points at where the sub-parser failed. When nesting
+    /// `attempt` calls, a failing inner call only rewinds to where that inner call started.
+    pub fn attempt<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        let start = self.mark();
+        let result = f(self);
+        if result.is_err() {
+            self.restore(start);
+        }
+        result
+    }
+
+    /// Try the `branches` in order, each starting from the current position, and return the
+    /// result of the first one that succeeds. If all of them fail, gives the given error at the
+    /// furthest position at which any branch failed.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError, ParserHelper};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Value {
+    ///     Bool(bool),
+    ///     Null,
+    /// }
+    ///
+    /// fn value(p: &mut ParserHelper) -> Result<Value, Error<MiniError>> {
+    ///     p.alt(
+    ///         &mut [
+    ///             &mut |p| p.expect_bytes(b"true", MiniError::Eoi).map(|_| Value::Bool(true)),
+    ///             &mut |p| p.expect_bytes(b"false", MiniError::Eoi).map(|_| Value::Bool(false)),
+    ///             &mut |p| p.expect_bytes(b"null", MiniError::Eoi).map(|_| Value::Null),
+    ///         ],
+    ///         MiniError::msg("expected a value"),
+    ///     )
+    /// }
+    ///
+    /// assert_eq!(value(&mut ParserHelper::new(b"false")), Ok(Value::Bool(false)));
+    /// assert_eq!(value(&mut ParserHelper::new(b"null")), Ok(Value::Null));
+    /// assert_eq!(
+    ///     value(&mut ParserHelper::new(b"nil")),
+    ///     Err(Error::new(0, MiniError::msg("expected a value"))),
+    /// );
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn alt<T, E>(
+        &mut self,
+        branches: &mut [&mut dyn FnMut(&mut Self) -> Result<T, Error<E>>],
+        err: E,
+    ) -> Result<T, Error<E>> {
+        let mut furthest = self.position();
+        for branch in branches.iter_mut() {
+            match self.attempt(|p| branch(p)) {
+                Ok(t) => return Ok(t),
+                Err(e) => furthest = furthest.max(e.position),
+            }
+        }
+        self.fail_at_position(err, furthest)
+    }
+
+    /// Run a sub-parser, and return `None` (restoring the prior position) if it fails.
+    pub fn optional<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Option<T> {
+        self.attempt(f).ok()
+    }
+
+    /// Same as `optional`, but errors that note an unexpected end of input (see `IsEoi`) are
+    /// propagated rather than turned into `None`, so that truncated input can be told apart from
+    /// absent input.
+    pub fn optional_or_err<T, E: IsEoi>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<Option<T>, Error<E>> {
+        match self.attempt(f) {
+            Ok(t) => Ok(Some(t)),
+            Err(err) if err.e.is_eoi() => Err(err),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Run `item` repeatedly until it fails, and fold the results with `acc`, starting from
+    /// `init`. The input consumed by the final, failing `item` is restored.
+    ///
+    /// Signals `E::no_progress` as soon as `item` succeeds without consuming any input, as running
+    /// it again would loop forever.
+    pub fn fold_many0<A, T, E: NoProgress>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error<E>>,
+        init: A,
+        mut acc: impl FnMut(A, T) -> A,
+    ) -> Result<A, Error<E>> {
+        let mut folded = init;
+        loop {
+            let start = self.position();
+            match self.attempt(&mut item) {
+                Ok(_) if self.position() == start => return self.fail(E::no_progress()),
+                Ok(t) => folded = acc(folded, t),
+                Err(_) => return Ok(folded),
+            }
+        }
+    }
+
+    /// Same as `fold_many0`, but returns the error of `item` if it does not succeed at least once.
+    pub fn fold_many1<A, T, E: NoProgress>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error<E>>,
+        init: A,
+        mut acc: impl FnMut(A, T) -> A,
+    ) -> Result<A, Error<E>> {
+        let start = self.position();
+        let first = self.attempt(&mut item)?;
+        if self.position() == start {
+            return self.fail(E::no_progress());
+        }
+        let init = acc(init, first);
+        self.fold_many0(item, init, acc)
+    }
+
+    /// Parse a list of `item`s separated by `sep`, calling `each` on every item, and return the
+    /// number of items. The list may be empty. `trailing` determines whether the list may or must
+    /// end with a separator; if trailing separators are forbidden, a separator that is not followed
+    /// by an item is not consumed.
+    ///
+    /// Also stops (without calling `each` on that item) as soon as a separator and the following
+    /// item succeed without consuming any input, as continuing would loop forever.
+    pub fn separated<T, E>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error<E>>,
+        mut sep: impl FnMut(&mut Self) -> Result<(), Error<E>>,
+        trailing: TrailingSep,
+        mut each: impl FnMut(T),
+    ) -> Result<usize, Error<E>> {
+        match self.attempt(&mut item) {
+            Ok(t) => each(t),
+            Err(_) => return Ok(0),
+        }
+
+        let mut count = 1;
+        loop {
+            let before_sep = self.mark();
+            if let Err(err) = self.attempt(&mut sep) {
+                return match trailing {
+                    TrailingSep::Required => Err(err),
+                    _ => Ok(count),
+                };
+            }
+
+            match self.attempt(&mut item) {
+                Ok(t) if self.position() != before_sep.position() => {
+                    each(t);
+                    count += 1;
+                }
+                Ok(_) => return Ok(count),
+                Err(_) => {
+                    if trailing == TrailingSep::Forbidden {
+                        self.restore(before_sep);
                     }
+                    return Ok(count);
                 }
             }
         }
     }
+
+    /// Parse an `open` delimiter, then `inner`, then a `close` delimiter, and return the result of
+    /// `inner`. Errors of all three parts are returned unchanged.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError, ParserHelper};
+    ///
+    /// // Parses a parenthesized, possibly nested, decimal number.
+    /// fn expr(p: &mut ParserHelper) -> Result<u64, Error<MiniError>> {
+    ///     if p.peek_or_end() == Some(b'(') {
+    ///         p.delimited_or(
+    ///             |p| p.expect(b'(', MiniError::msg("expected '('")),
+    ///             expr,
+    ///             |p| p.expect(b')', MiniError::msg("expected ')'")),
+    ///             MiniError::msg("unclosed '(' started here"),
+    ///         )
+    ///     } else {
+    ///         let digits = p.take_ascii_digits_nonempty(MiniError::msg("expected a number"))?;
+    ///         Ok(std::str::from_utf8(digits).unwrap().parse().unwrap())
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(expr(&mut ParserHelper::new(b"((42))")), Ok(42));
+    /// assert_eq!(
+    ///     expr(&mut ParserHelper::new(b"((42)")),
+    ///     Err(Error::new(0, MiniError::msg("unclosed '(' started here"))),
+    /// );
+    ///
+    /// // Without `delimited_or`, the error points at where the closing delimiter was expected.
+    /// let mut p = ParserHelper::new(b"(42");
+    /// assert_eq!(
+    ///     p.delimited(
+    ///         |p| p.expect(b'(', MiniError::msg("expected '('")),
+    ///         |p| p.take_ascii_digits_nonempty(MiniError::msg("expected a number")),
+    ///         |p| p.expect(b')', MiniError::msg("expected ')'")),
+    ///     ),
+    ///     Err(Error::new(3, MiniError::Eoi)),
+    /// );
+    /// ```
+    pub fn delimited<T, E>(
+        &mut self,
+        open: impl FnOnce(&mut Self) -> Result<(), Error<E>>,
+        inner: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+        close: impl FnOnce(&mut Self) -> Result<(), Error<E>>,
+    ) -> Result<T, Error<E>> {
+        open(self)?;
+        let t = inner(self)?;
+        close(self)?;
+        Ok(t)
+    }
+
+    /// Same as `delimited`, but if `close` fails, gives the `unclosed` error at the position where
+    /// the opening delimiter started.
+    pub fn delimited_or<T, E>(
+        &mut self,
+        open: impl FnOnce(&mut Self) -> Result<(), Error<E>>,
+        inner: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+        close: impl FnOnce(&mut Self) -> Result<(), Error<E>>,
+        unclosed: E,
+    ) -> Result<T, Error<E>> {
+        let start = self.position();
+        open(self)?;
+        let t = inner(self)?;
+        match close(self) {
+            Ok(()) => Ok(t),
+            Err(_) => self.fail_at_position(unclosed, start),
+        }
+    }
+
+    /// Obtain the span from `start` up to the current position.
+    pub fn current_span_since(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.position(),
+        }
+    }
+
+    /// Run `item` repeatedly, calling `each` on every result, until `until` succeeds, and return
+    /// the number of items. `until` is tried before every item, and does not consume any input
+    /// if it fails. If both `until` and `item` fail, the error of `item` is returned. If `item`
+    /// succeeds without consuming any input, running it again would loop forever, so the error of
+    /// `until` is returned instead.
+    ///
+    /// ```
+    /// use atm_parser_helper::{Error, MiniError, ParserHelper};
+    ///
+    /// fn block_comment(p: &mut ParserHelper) -> Result<usize, Error<MiniError>> {
+    ///     p.expect_bytes(b"/*", MiniError::msg("expected a comment"))?;
+    ///     p.many_till(|p| p.next(), |p| p.expect_bytes(b"*/", MiniError::Eoi), |_| {})
+    /// }
+    ///
+    /// let mut p = ParserHelper::new(b"/* hi */;");
+    /// assert_eq!(block_comment(&mut p), Ok(4));
+    /// assert_eq!(p.rest(), b";");
+    ///
+    /// assert_eq!(block_comment(&mut ParserHelper::new(b"/**/")), Ok(0));
+    /// assert_eq!(
+    ///     block_comment(&mut ParserHelper::new(b"/* hi *")),
+    ///     Err(Error::new(7, MiniError::Eoi)),
+    /// );
+    /// ```
+    pub fn many_till<T, E>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error<E>>,
+        mut until: impl FnMut(&mut Self) -> Result<(), Error<E>>,
+        mut each: impl FnMut(T),
+    ) -> Result<usize, Error<E>> {
+        let mut count = 0;
+        loop {
+            let until_err = match self.attempt(&mut until) {
+                Ok(()) => return Ok(count),
+                Err(err) => err,
+            };
+
+            let start = self.position();
+            let t = self.attempt(&mut item)?;
+            if self.position() == start {
+                return Err(until_err);
+            }
+            each(t);
+            count += 1;
+        }
+    }
+
+    /// Run `item` exactly `n` times, passing it the index of the current iteration, and call
+    /// `each` on every result. Stops at the first error, without restoring any consumed input.
+    pub fn repeat<T, E>(
+        &mut self,
+        n: usize,
+        mut item: impl FnMut(&mut Self, usize) -> Result<T, Error<E>>,
+        mut each: impl FnMut(T),
+    ) -> Result<(), Error<E>> {
+        for i in 0..n {
+            each(item(self, i)?);
+        }
+        Ok(())
+    }
+
+    /// Same as `repeat`, but collects the results into a `Vec`. The up-front allocation is
+    /// bounded by the remaining input length, so that a hostile `n` cannot exhaust memory.
+    pub fn repeat_collect<T, E>(
+        &mut self,
+        n: usize,
+        item: impl FnMut(&mut Self, usize) -> Result<T, Error<E>>,
+    ) -> Result<Vec<T>, Error<E>> {
+        let mut items = Vec::with_capacity(n.min(self.remaining_len()));
+        self.repeat(n, item, |t| items.push(t))?;
+        Ok(items)
+    }
+
+    /// Run a sub-parser, and pair its result with the span of input it consumed.
+    pub fn with_span<T, E, F>(&mut self, f: F) -> Result<Spanned<T>, Error<E>>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error<E>>,
+    {
+        let start = self.position();
+        let value = f(self)?;
+        Ok(Spanned::new(value, self.current_span_since(start)))
+    }
+
+    /// Run a sub-parser, and return its result together with the input it consumed.
+    pub fn recognize<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<(T, &'a [u8]), Error<E>> {
+        let start = self.position();
+        let t = f(self)?;
+        Ok((t, self.slice_since(start)))
+    }
+
+    /// Wrap this helper so that the line and column of its position can be queried cheaply and
+    /// repeatedly.
+    ///
+    /// ```
+    /// use atm_parser_helper::{MiniError, ParserHelper};
+    ///
+    /// let mut p = ParserHelper::new(b"ab\r\ncd");
+    /// let mut p = p.with_line_col_tracker();
+    /// p.advance(1);
+    /// assert_eq!(p.current_line_col(), (1, 2));
+    /// p.advance(3);
+    /// assert_eq!(p.current_line_col(), (2, 1));
+    /// p.advance(2);
+    /// let err = p.fail_with_location::<(), _>(MiniError::Eoi).unwrap_err();
+    /// assert_eq!((err.position, err.line_col.line, err.line_col.col), (6, 2, 3));
+    /// ```
+    pub fn with_line_col_tracker(&mut self) -> LineColTrackerSession<'_, 'a> {
+        LineColTrackerSession {
+            p: self,
+            tracker: Cell::new(LineColTracker::new()),
+        }
+    }
+
+    /// Wrap this helper so that the error that got furthest into the input is kept, even if the
+    /// parser recovers from it later on, e.g., by backtracking. Errors are kept if they are
+    /// produced through the fail methods of the wrapper, or passed through `track`.
+    ///
+    /// ```
+    /// use atm_parser_helper::{MiniError, ParserHelper};
+    ///
+    /// let mut p = ParserHelper::new(b"ab");
+    /// let mut p = p.with_furthest_error::<MiniError>();
+    /// p.advance(1);
+    /// assert!(p.fail::<()>(MiniError::msg("expected 'c'")).is_err());
+    /// p.advance_back(1);
+    /// assert!(p.fail::<()>(MiniError::msg("expected 'x'")).is_err());
+    /// let res = p.next();
+    /// assert!(p.track(res).is_ok());
+    ///
+    /// assert_eq!(p.furthest_failure(), Some(1));
+    /// assert_eq!(p.furthest_error().unwrap().e, MiniError::msg("expected 'c'"));
+    /// ```
+    pub fn with_furthest_error<E>(&mut self) -> FurthestErrorSession<'_, 'a, E> {
+        FurthestErrorSession {
+            p: self,
+            furthest: FurthestError::new(),
+        }
+    }
+
+    /// Wrap this helper so that the labels of failed expectations can be collected, for errors
+    /// like "expected ',', ']' or a string".
+    ///
+    /// ```
+    /// use atm_parser_helper::{Eoi, ParserHelper, Unexpected};
+    ///
+    /// #[derive(Debug)]
+    /// struct Found(Option<u8>);
+    ///
+    /// impl Eoi for Found {
+    ///     fn eoi() -> Self {
+    ///         Found(None)
+    ///     }
+    /// }
+    ///
+    /// impl Unexpected for Found {
+    ///     fn unexpected(found: u8) -> Self {
+    ///         Found(Some(found))
+    ///     }
+    /// }
+    ///
+    /// let mut p = ParserHelper::new(b"[1x");
+    /// let mut p = p.with_expectations();
+    /// p.advance(2);
+    /// assert!(p.expect_labeled::<Found>(b',', "','").is_err());
+    /// assert!(p.expect_labeled::<Found>(b']', "']'").is_err());
+    /// assert_eq!(p.expectations(), ["','", "']'"]);
+    ///
+    /// p.advance(1);
+    /// assert!(p.expectations().is_empty());
+    /// ```
+    pub fn with_expectations(&mut self) -> ExpectationsSession<'_, 'a> {
+        ExpectationsSession {
+            p: self,
+            expectations: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Run a sub-parser, and if it fails, add `label` and the position at which the sub-parser
+    /// started to the context of its error. Since the sub-parser may itself return an
+    /// `ErrorContext`, calls to `within` can be nested.
+    ///
+    /// ```
+    /// use atm_parser_helper::{ErrorContext, MiniError, ParserHelper};
+    ///
+    /// fn imports(p: &mut ParserHelper) -> Result<u8, ErrorContext<MiniError>> {
+    ///     p.within("imports", |p| {
+    ///         p.expect(b'[', MiniError::msg("expected '['"))?;
+    ///         p.within("array element", |p| p.next())
+    ///     })
+    /// }
+    ///
+    /// let err = imports(&mut ParserHelper::new(b"[")).unwrap_err();
+    /// let context: Vec<_> = err.context.iter().collect();
+    /// assert_eq!(context, [("imports", 0), ("array element", 1)]);
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "while parsing imports (at 0): while parsing array element (at 1): \
+    ///      parse error at position 1: unexpected end of input",
+    /// );
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn within<T, E, X: Into<ErrorContext<E>>>(
+        &mut self,
+        label: &'static str,
+        f: impl FnOnce(&mut Self) -> Result<T, X>,
+    ) -> Result<T, ErrorContext<E>> {
+        let start = self.position();
+        f(self).map_err(|err| {
+            let mut err = err.into();
+            err.context.push_outer(label, start);
+            err
+        })
+    }
+
+    /// Turn this helper into an iterator that runs `f` once per item, until the input is
+    /// exhausted. After `f` returns an error, the iterator yields that error and then stops.
+    ///
+    /// If `f` succeeds without consuming any input, the iterator yields `E::no_progress` instead
+    /// of the record and then stops, as continuing would loop forever.
+    pub fn records<T, E, F>(self, f: F) -> Records<'a, F>
+    where
+        E: NoProgress,
+        F: FnMut(&mut ParserHelper<'a>) -> Result<T, Error<E>>,
+    {
+        Records {
+            p: self,
+            f,
+            done: false,
+        }
+    }
+
+    /// Run a sub-parser, and if it fails, record its error in `acc` and run `recover` to skip from
+    /// wherever the sub-parser stopped to a point from which parsing can resume. Returns `None` if
+    /// the sub-parser failed.
+    pub fn recovering<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+        recover: impl FnOnce(&mut Self),
+        acc: &mut ErrorAccumulator<E>,
+    ) -> Option<T> {
+        match f(self) {
+            Ok(t) => Some(t),
+            Err(err) => {
+                acc.record(err);
+                recover(self);
+                None
+            }
+        }
+    }
+
+    /// Consume an ASCII decimal integer without a sign, and return its value. Signals unexpected
+    /// end of the input if the input is exhausted, an unexpected byte if it does not start with a
+    /// digit, and overflow (without consuming anything) if the number is larger than `u64::MAX`.
+    pub fn parse_u64_decimal<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<u64, Error<E>> {
+        self.parse_digits(10, u64::MAX)
+    }
+
+    /// Consume an ASCII decimal integer with an optional leading `+` or `-`, and return its
+    /// value. Signals unexpected end of the input if the input is exhausted before the first
+    /// digit, an unexpected byte if a non-digit comes instead, and overflow (without consuming
+    /// anything) if the number does not fit into an `i64`.
+    pub fn parse_i64_decimal<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<i64, Error<E>> {
+        let start = self.position();
+        self.attempt(|p| {
+            let negative = p.peek_or_end() == Some(b'-');
+            if negative || p.peek_or_end() == Some(b'+') {
+                p.advance(1);
+            }
+
+            if negative {
+                let magnitude = p.parse_digits_since(start, 10, i64::MIN.unsigned_abs())?;
+                Ok(0i64.wrapping_sub_unsigned(magnitude))
+            } else {
+                Ok(p.parse_digits_since(start, 10, i64::MAX as u64)? as i64)
+            }
+        })
+    }
+
+    /// Consume ASCII hexadecimal digits (of either case, without a `0x` prefix), and return their
+    /// value. Signals unexpected end of the input if the input is exhausted, an unexpected byte if
+    /// it does not start with a digit, and overflow (without consuming anything) if the number is
+    /// larger than `u32::MAX`.
+    pub fn parse_u32_hex<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<u32, Error<E>> {
+        self.parse_digits(16, u64::from(u32::MAX)).map(|n| n as u32)
+    }
+
+    /// Consume ASCII hexadecimal digits (of either case, without a `0x` prefix), and return their
+    /// value. Signals unexpected end of the input if the input is exhausted, an unexpected byte if
+    /// it does not start with a digit, and overflow (without consuming anything) if the number is
+    /// larger than `u64::MAX`.
+    pub fn parse_u64_hex<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<u64, Error<E>> {
+        self.parse_digits(16, u64::MAX)
+    }
+
+    /// Consume ASCII octal digits, and return their value. Signals unexpected end of the input if
+    /// the input is exhausted, an unexpected byte if it does not start with a digit, and overflow
+    /// (without consuming anything) if the number is larger than `u32::MAX`.
+    pub fn parse_u32_octal<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<u32, Error<E>> {
+        self.parse_digits(8, u64::from(u32::MAX)).map(|n| n as u32)
+    }
+
+    /// Consume ASCII binary digits, and return their value. Signals unexpected end of the input if
+    /// the input is exhausted, an unexpected byte if it does not start with a digit, and overflow
+    /// (without consuming anything) if the number is larger than `u32::MAX`.
+    pub fn parse_u32_binary<E: Eoi + Unexpected + Overflow>(&mut self) -> Result<u32, Error<E>> {
+        self.parse_digits(2, u64::from(u32::MAX)).map(|n| n as u32)
+    }
+
+    /// Consume ASCII digits in the given radix, and return their value. Digits above nine are
+    /// letters of either case, `a` being ten. Signals unexpected end of the input if the input is
+    /// exhausted, an unexpected byte if it does not start with a digit, and overflow (without
+    /// consuming anything) if the number is larger than `u64::MAX`.
+    ///
+    /// Panics if `radix` is not between 2 and 36 (inclusive).
+    pub fn parse_radix_int<E: Eoi + Unexpected + Overflow>(
+        &mut self,
+        radix: u8,
+    ) -> Result<u64, Error<E>> {
+        assert!((2..=36).contains(&radix), "radix {} is not between 2 and 36", radix);
+        self.parse_digits(u32::from(radix), u64::MAX)
+    }
+
+    /// Consume a nonempty sequence of digits in the given radix, and return their value. Signals
+    /// overflow (without consuming anything) if the value would exceed `max`.
+    fn parse_digits<E: Eoi + Unexpected + Overflow>(
+        &mut self,
+        radix: u32,
+        max: u64,
+    ) -> Result<u64, Error<E>> {
+        self.parse_digits_since(self.position(), radix, max)
+    }
+
+    /// Same as `parse_digits`, but on overflow moves back to and reports the error at `start`,
+    /// which may lie before the digits, e.g., at a sign.
+    fn parse_digits_since<E: Eoi + Unexpected + Overflow>(
+        &mut self,
+        start: usize,
+        radix: u32,
+        max: u64,
+    ) -> Result<u64, Error<E>> {
+        let first = self.peek()?;
+        let mut value = match char::from(first).to_digit(radix) {
+            Some(digit) => u64::from(digit),
+            None => return self.fail(E::unexpected(first)),
+        };
+        self.advance(1);
+
+        while let Some(digit) = self.peek_or_end().and_then(|b| char::from(b).to_digit(radix)) {
+            match value
+                .checked_mul(u64::from(radix))
+                .and_then(|v| v.checked_add(u64::from(digit)))
+            {
+                Some(v) if v <= max => value = v,
+                _ => {
+                    self.position = start;
+                    return self.fail(E::overflow());
+                }
+            }
+            self.advance(1);
+        }
+        Ok(value)
+    }
+}
+
+/// Whether a list parsed by `ParserHelper::separated` may end with a separator.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TrailingSep {
+    /// A separator after the last item is not consumed.
+    Forbidden,
+    /// A separator after the last item is consumed if present.
+    Allowed,
+    /// Every item must be followed by a separator.
+    Required,
+}
+
+/// An iterator that repeatedly runs a parser until the input is exhausted. Obtained via
+/// `ParserHelper::records`.
+pub struct Records<'a, F> {
+    p: ParserHelper<'a>,
+    f: F,
+    done: bool,
+}
+
+impl<'a, T, E, F> Iterator for Records<'a, F>
+where
+    E: NoProgress,
+    F: FnMut(&mut ParserHelper<'a>) -> Result<T, Error<E>>,
+{
+    type Item = Result<T, Error<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || !self.p.has_remaining() {
+            self.done = true;
+            return None;
+        }
+
+        let start = self.p.position();
+        let result = match (self.f)(&mut self.p) {
+            Ok(_) if self.p.position() == start => self.p.fail(E::no_progress()),
+            result => result,
+        };
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+impl<'a, T, E, F> FusedIterator for Records<'a, F>
+where
+    E: NoProgress,
+    F: FnMut(&mut ParserHelper<'a>) -> Result<T, Error<E>>,
+{
+}
+
+/// A range of positions in the input, from `start` (inclusive) to `end` (exclusive).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The number of bytes covered by this span.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Whether this span covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Obtain the part of `input` covered by this span. Like `len`, treats a span whose start lies
+    /// after its end as empty. Panics if the span reaches beyond the end of `input`.
+    pub fn slice_of<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.start.min(self.end)..self.end]
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A value together with the span of input it was parsed from.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    /// Pair a value with its span.
+    pub fn new(value: T, span: Span) -> Self {
+        Spanned { value, span }
+    }
+}
+
+/// A guard that restores the position of a `ParserHelper` when dropped, unless it has been
+/// committed. Obtained via `ParserHelper::transaction`.
+pub struct Transaction<'p, 'a> {
+    p: &'p mut ParserHelper<'a>,
+    start: Mark<'a>,
+    committed: bool,
+}
+
+impl<'p, 'a> Transaction<'p, 'a> {
+    /// Keep the progress made during the transaction.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl<'p, 'a> Deref for Transaction<'p, 'a> {
+    type Target = ParserHelper<'a>;
+
+    fn deref(&self) -> &ParserHelper<'a> {
+        self.p
+    }
+}
+
+impl<'p, 'a> DerefMut for Transaction<'p, 'a> {
+    fn deref_mut(&mut self) -> &mut ParserHelper<'a> {
+        self.p
+    }
+}
+
+impl<'p, 'a> Drop for Transaction<'p, 'a> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.p.restore(self.start);
+        }
+    }
+}
+
+/// Incrementally computes line and column numbers (both starting at one, columns counting bytes
+/// rather than UTF-8 characters) while moving through an input. Lines are terminated by `\n`, so
+/// `\r\n` counts as a single line break. Moving forward only scans the newly covered bytes,
+/// moving backward rescans from the start of the input.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct LineColTracker {
+    position: usize,
+    line: usize,
+    col: usize,
+}
+
+impl LineColTracker {
+    /// Create a tracker at the start of the input.
+    pub fn new() -> Self {
+        LineColTracker {
+            position: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    /// The position up to which the tracker has scanned.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The line and column of the tracked position.
+    pub fn line_col(&self) -> (usize, usize) {
+        (self.line, self.col)
+    }
+
+    /// Move to the given position within `input`, which must be the same input on every call. A
+    /// position beyond the input is treated as the end of the input.
+    pub fn advance_to(&mut self, input: &[u8], position: usize) {
+        let position = position.min(input.len());
+        if position < self.position {
+            *self = LineColTracker::new();
+        }
+
+        for b in &input[self.position..position] {
+            if *b == b'\n' {
+                self.line += 1;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
+        }
+        self.position = position;
+    }
+}
+
+impl Default for LineColTracker {
+    fn default() -> Self {
+        LineColTracker::new()
+    }
+}
+
+/// A `ParserHelper` that also keeps track of the line and column of its position. Obtained via
+/// `ParserHelper::with_line_col_tracker`.
+pub struct LineColTrackerSession<'p, 'a> {
+    p: &'p mut ParserHelper<'a>,
+    tracker: Cell<LineColTracker>,
+}
+
+impl<'p, 'a> LineColTrackerSession<'p, 'a> {
+    /// The line and column (both starting at one) of the current position. Only scans the input
+    /// consumed since the last call.
+    pub fn current_line_col(&self) -> (usize, usize) {
+        let mut tracker = self.tracker.get();
+        tracker.advance_to(self.p.input, self.p.position());
+        self.tracker.set(tracker);
+        tracker.line_col()
+    }
+
+    /// The line (starting at one) of the current position.
+    pub fn line(&self) -> usize {
+        self.current_line_col().0
+    }
+
+    /// The column (starting at one, counting bytes) of the current position.
+    pub fn column(&self) -> usize {
+        self.current_line_col().1
+    }
+
+    /// Produce an error at the current position, including its line and column.
+    pub fn fail_with_location<T, E>(&self, reason: E) -> Result<T, LocatedError<E>> {
+        let (line, col) = self.current_line_col();
+        self.p.fail(reason).map_err(|err| LocatedError {
+            position: err.position,
+            line_col: LineColPosition { line, col },
+            e: err.e,
+        })
+    }
+}
+
+impl<'p, 'a> Deref for LineColTrackerSession<'p, 'a> {
+    type Target = ParserHelper<'a>;
+
+    fn deref(&self) -> &ParserHelper<'a> {
+        self.p
+    }
+}
+
+impl<'p, 'a> DerefMut for LineColTrackerSession<'p, 'a> {
+    fn deref_mut(&mut self) -> &mut ParserHelper<'a> {
+        self.p
+    }
+}
+
+/// A `ParserHelper` that also keeps the error that got furthest into the input. Obtained via
+/// `ParserHelper::with_furthest_error`.
+pub struct FurthestErrorSession<'p, 'a, E> {
+    p: &'p mut ParserHelper<'a>,
+    furthest: FurthestError<E>,
+}
+
+impl<'p, 'a, E> FurthestErrorSession<'p, 'a, E> {
+    /// The furthest error produced so far, even if it was recovered from later on.
+    pub fn furthest_error(&self) -> Option<&Error<E>> {
+        self.furthest.furthest()
+    }
+
+    /// The position of the furthest error produced so far.
+    pub fn furthest_failure(&self) -> Option<usize> {
+        self.furthest_error().map(|err| err.position)
+    }
+
+    /// Forget about all errors produced so far, and return the furthest one.
+    pub fn take_furthest_error(&mut self) -> Option<Error<E>> {
+        self.furthest.take_furthest()
+    }
+}
+
+impl<'p, 'a, E: Clone> FurthestErrorSession<'p, 'a, E> {
+    /// Keep a copy of the error of `result` if it is the furthest one so far, and return `result`
+    /// unchanged. Use this for errors that were not produced by the fail methods of the session,
+    /// such as those of `next` or of sub-parsers.
+    pub fn track<T>(&mut self, result: Result<T, Error<E>>) -> Result<T, Error<E>> {
+        if let Err(err) = &result {
+            self.furthest.record(err.clone());
+        }
+        result
+    }
+
+    /// Produce an error at the current position, and keep a copy of it if it is the furthest one.
+    pub fn fail<T>(&mut self, reason: E) -> Result<T, Error<E>> {
+        let position = self.p.position();
+        self.fail_at_position(reason, position)
+    }
+
+    /// Produce an error at the given position, and keep a copy of it if it is the furthest one.
+    pub fn fail_at_position<T>(&mut self, reason: E, position: usize) -> Result<T, Error<E>> {
+        let res = self.p.fail_at_position(reason, position);
+        self.track(res)
+    }
+
+    /// Produce an error spanning from `start` to `end`, and keep a copy of it at position `end`
+    /// if that is the furthest one.
+    pub fn fail_spanned<T>(
+        &mut self,
+        reason: E,
+        start: usize,
+        end: usize,
+    ) -> Result<T, ErrorSpan<E>> {
+        self.furthest.record(Error::new(end, reason.clone()));
+        self.p.fail_spanned(reason, start, end)
+    }
+
+    /// Produce an error spanning from `start` to the current position, and keep a copy of it at
+    /// the current position if that is the furthest one.
+    pub fn fail_spanning<T>(&mut self, start: usize, e: E) -> Result<T, ErrorSpan<E>> {
+        let end = self.p.position();
+        self.fail_spanned(e, start, end)
+    }
+}
+
+impl<'p, 'a, E: Clone + Eoi> FurthestErrorSession<'p, 'a, E> {
+    /// Produce an error indicating the unexpected end of the input at the current position, and
+    /// keep a copy of it if it is the furthest one.
+    pub fn unexpected_end_of_input<T>(&mut self) -> Result<T, Error<E>> {
+        self.fail(E::eoi())
+    }
+
+    /// Produce an error indicating the unexpected end of the input at the current position, where
+    /// `needed` more bytes of input would have been required, and keep a copy of it if it is the
+    /// furthest one.
+    pub fn unexpected_end_of_input_needing<T>(&mut self, needed: usize) -> Result<T, Error<E>> {
+        self.fail(E::eoi_needing(needed))
+    }
+}
+
+impl<'p, 'a, E> Deref for FurthestErrorSession<'p, 'a, E> {
+    type Target = ParserHelper<'a>;
+
+    fn deref(&self) -> &ParserHelper<'a> {
+        self.p
+    }
+}
+
+impl<'p, 'a, E> DerefMut for FurthestErrorSession<'p, 'a, E> {
+    fn deref_mut(&mut self) -> &mut ParserHelper<'a> {
+        self.p
+    }
+}
+
+/// A `ParserHelper` that also collects the labels of all expectations that failed at the furthest
+/// position at which any expectation failed. Obtained via `ParserHelper::with_expectations`.
+///
+/// The labels are forgotten once the parser has successfully moved beyond their position, even if
+/// it backtracks later on. Progress is noticed between calls made through the session, so a call
+/// that fails and leaves the position where it was, such as a failing `attempt`, keeps the labels.
+pub struct ExpectationsSession<'p, 'a> {
+    p: &'p mut ParserHelper<'a>,
+    expectations: Vec<&'static str>,
+    position: usize,
+}
+
+impl<'p, 'a> ExpectationsSession<'p, 'a> {
+    /// Same as `expect_found`, but also records `label` as an expectation if `expected` is not
+    /// found.
+    pub fn expect_labeled<E: Eoi + Unexpected>(
+        &mut self,
+        expected: u8,
+        label: &'static str,
+    ) -> Result<(), Error<E>> {
+        self.forget_if_moved_beyond();
+        self.p.expect_found(expected).inspect_err(|_| self.record_expectation(label))
+    }
+
+    /// Record that `label` would have been a valid continuation of the input at the current
+    /// position.
+    pub fn record_expectation(&mut self, label: &'static str) {
+        self.forget_if_moved_beyond();
+        let position = self.p.position();
+        if self.expectations.is_empty() {
+            self.position = position;
+        }
+
+        if position == self.position && !self.expectations.contains(&label) {
+            self.expectations.push(label);
+        }
+    }
+
+    /// The labels of all expectations that failed at the furthest position at which any
+    /// expectation failed, unless the parser has moved beyond that position since.
+    pub fn expectations(&self) -> &[&'static str] {
+        if self.p.position() > self.position {
+            &[]
+        } else {
+            &self.expectations
+        }
+    }
+
+    /// Forget the recorded labels if the parser has moved beyond their position.
+    fn forget_if_moved_beyond(&mut self) {
+        if self.p.position() > self.position {
+            self.expectations.clear();
+        }
+    }
+}
+
+impl<'p, 'a> Deref for ExpectationsSession<'p, 'a> {
+    type Target = ParserHelper<'a>;
+
+    fn deref(&self) -> &ParserHelper<'a> {
+        self.p
+    }
+}
+
+impl<'p, 'a> DerefMut for ExpectationsSession<'p, 'a> {
+    fn deref_mut(&mut self) -> &mut ParserHelper<'a> {
+        self.forget_if_moved_beyond();
+        self.p
+    }
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[cfg(feature = "memchr")]
+fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    memchr::memchr(needle, haystack)
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|c| *c == needle)
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[cfg(feature = "memchr")]
+fn find_slice(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    memchr::memmem::find(haystack, needle)
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+fn find_slice(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        Some(0)
+    } else {
+        haystack.windows(needle.len()).position(|window| window == needle)
+    }
+}
+
+/// A set of bytes, represented as a bitmap.
+struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    fn new(bytes: &[u8]) -> Self {
+        let mut set = ByteSet([0; 4]);
+        for b in bytes {
+            set.0[usize::from(b >> 6)] |= 1 << (b & 63);
+        }
+        set
+    }
+
+    fn contains(&self, b: u8) -> bool {
+        self.0[usize::from(b >> 6)] & (1 << (b & 63)) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    enum TestError {
+        Eoi,
+        Needed(usize),
+        Unexpected(u8),
+        Overflow,
+    }
+
+    impl Eoi for TestError {
+        fn eoi() -> Self {
+            TestError::Eoi
+        }
+
+        fn eoi_needing(needed: usize) -> Self {
+            TestError::Needed(needed)
+        }
+    }
+
+    impl IsEoi for TestError {
+        fn is_eoi(&self) -> bool {
+            matches!(self, TestError::Eoi | TestError::Needed(_))
+        }
+    }
+
+    impl Unexpected for TestError {
+        fn unexpected(found: u8) -> Self {
+            TestError::Unexpected(found)
+        }
+    }
+
+    impl Overflow for TestError {
+        fn overflow() -> Self {
+            TestError::Overflow
+        }
+    }
+
+    #[test]
+    fn take_nothing() {
+        let mut p = ParserHelper::new(b"abc");
+        assert_eq!(p.take::<MiniError>(0), Ok(&b""[..]));
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn take_exactly_the_remaining_input() {
+        let mut p = ParserHelper::new(b"abc");
+        p.advance(1);
+        assert_eq!(p.take::<MiniError>(2), Ok(&b"bc"[..]));
+        assert_eq!(p.position(), 3);
+    }
+
+    #[test]
+    fn take_one_past_the_end() {
+        let mut p = ParserHelper::new(b"abc");
+        p.advance(1);
+        assert_eq!(p.take::<MiniError>(3), Err(Error::new(1, MiniError::Eoi)));
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn taken_slices_outlive_the_helper() {
+        let input = b"abc";
+        let taken = {
+            let mut p = ParserHelper::new(input);
+            p.take::<MiniError>(2).unwrap()
+        };
+        assert_eq!(taken, b"ab");
+    }
+
+    #[test]
+    fn take_while1_on_empty_input_gives_the_user_error() {
+        let mut p = ParserHelper::new(b"");
+        assert_eq!(
+            p.take_while1(|c| c.is_ascii_digit(), MiniError::msg("digit")),
+            Err(Error::new(0, MiniError::msg("digit"))),
+        );
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn take_while1_on_immediate_mismatch_gives_the_user_error() {
+        let mut p = ParserHelper::new(b"x1");
+        assert_eq!(
+            p.take_while1(|c| c.is_ascii_digit(), MiniError::msg("digit")),
+            Err(Error::new(0, MiniError::msg("digit"))),
+        );
+        assert_eq!(p.position(), 0);
+        p.advance(1);
+        assert_eq!(p.take_while1(|c| c.is_ascii_digit(), MiniError::msg("digit")), Ok(&b"1"[..]));
+    }
+
+    #[test]
+    fn peek_slice_does_not_move_the_position() {
+        let mut p = ParserHelper::new(b"<<=x");
+        p.advance(1);
+        assert_eq!(p.peek_slice::<MiniError>(2), Ok(&b"<="[..]));
+        assert_eq!(p.peek_slice::<MiniError>(4), Err(Error::new(1, MiniError::Eoi)));
+        assert_eq!(p.peek_slice_or_end(2), b"<=");
+        assert_eq!(p.peek_slice_or_end(9), b"<=x");
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn peek_array_of_length_zero() {
+        let p = ParserHelper::new(b"");
+        assert_eq!(p.peek_array::<0, MiniError>(), Ok([]));
+    }
+
+    #[test]
+    fn peek_array_of_the_exact_remaining_length() {
+        let mut p = ParserHelper::new(b"abcd");
+        p.advance(1);
+        assert_eq!(p.peek_array::<3, MiniError>(), Ok(*b"bcd"));
+        assert_eq!(p.peek_array::<4, MiniError>(), Err(Error::new(1, MiniError::Eoi)));
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn rest_starts_with_empty_tag() {
+        let mut p = ParserHelper::new(b"ab");
+        assert!(p.rest_starts_with(b""));
+        p.advance(2);
+        assert!(p.rest_starts_with(b""));
+    }
+
+    #[test]
+    fn rest_starts_with_tag_longer_than_the_input() {
+        let p = ParserHelper::new(b"ab");
+        assert!(!p.rest_starts_with(b"abc"));
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn rest_starts_with_match_at_the_end() {
+        let mut p = ParserHelper::new(b"xab");
+        p.advance(1);
+        assert!(p.rest_starts_with(b"ab"));
+        assert!(p.peek_matches(|c| c == b'a'));
+        p.advance(2);
+        assert!(!p.peek_matches(|_| true));
+        assert_eq!(p.position(), 3);
+    }
+
+    #[test]
+    fn no_case_matching_with_mixed_case() {
+        let mut p = ParserHelper::new(b"eHlO Content-LENGTH");
+        assert!(p.advance_over_no_case(b"EHLO"));
+        p.advance(1);
+        assert_eq!(p.expect_bytes_no_case(b"content-length", MiniError::Eoi), Ok(()));
+        assert!(!p.has_remaining());
+    }
+
+    #[test]
+    fn no_case_matching_compares_non_ascii_verbatim() {
+        let mut p = ParserHelper::new(b"\xc4x");
+        assert!(!p.advance_over_no_case(b"\xe4x"));
+        assert_eq!(
+            p.expect_bytes_no_case(b"\xc4X\xff", MiniError::Eoi),
+            Err(Error::new(2, MiniError::Eoi)),
+        );
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn no_case_matching_straddling_the_end() {
+        let mut p = ParserHelper::new(b"xEH");
+        p.advance(1);
+        assert!(!p.advance_over_no_case(b"ehlo"));
+        assert_eq!(
+            p.expect_bytes_no_case(b"ehlo", MiniError::msg("ehlo")),
+            Err(Error::new(3, MiniError::msg("ehlo"))),
+        );
+        assert_eq!(
+            p.expect_bytes_no_case(b"ex", MiniError::msg("ex")),
+            Err(Error::new(2, MiniError::msg("ex"))),
+        );
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn none_of_with_empty_set() {
+        let mut p = ParserHelper::new(b"ab");
+        assert_eq!(p.none_of(b"", MiniError::msg("x")), Ok(b'a'));
+        assert_eq!(p.take_while_none_of(b""), b"b");
+    }
+
+    #[test]
+    fn none_of_rejects_members_without_consuming() {
+        let mut p = ParserHelper::new(b"ab\"c");
+        assert_eq!(p.take_while_none_of(b"\"\\\n"), b"ab");
+        assert_eq!(
+            p.none_of(b"\"\\\n", MiniError::msg("quote")),
+            Err(Error::new(2, MiniError::msg("quote"))),
+        );
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn none_of_at_the_end_of_input() {
+        let mut p = ParserHelper::new(b"a");
+        p.advance(1);
+        assert_eq!(p.none_of(b"x", MiniError::msg("x")), Err(Error::new(1, MiniError::Eoi)));
+        assert_eq!(p.take_while_none_of(b"x"), b"");
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn byte_range_inclusive_bounds() {
+        let mut p = ParserHelper::new(b"09a");
+        assert_eq!(p.expect_byte_range(b'0', b'9', MiniError::Eoi), Ok(b'0'));
+        assert_eq!(p.expect_byte_range(b'9', b'9', MiniError::Eoi), Ok(b'9'));
+        assert_eq!(p.take_while_in_range(b'0', b'9'), b"");
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn degenerate_byte_range_always_fails() {
+        let mut p = ParserHelper::new(b"5");
+        assert_eq!(
+            p.expect_byte_range(b'9', b'0', MiniError::msg("digit")),
+            Err(Error::new(0, MiniError::msg("digit"))),
+        );
+        assert_eq!(p.take_while_in_range(b'9', b'0'), b"");
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn rejecting_next_if_never_advances() {
+        let mut p = ParserHelper::new(b"-1");
+        assert_eq!(p.next_if(|c| c == b'+'), None);
+        assert!(!p.next_if_eq(b'+'));
+        assert_eq!(p.position(), 0);
+        assert!(p.next_if_eq(b'-'));
+        assert_eq!(p.next_if(|c| c.is_ascii_digit()), Some(b'1'));
+        assert_eq!(p.next_if(|_| true), None);
+        assert!(!p.next_if_eq(b'1'));
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn predicates_may_capture_mutable_state() {
+        let mut p = ParserHelper::new(b"a\"b\"c\"d");
+        let mut quotes = 0;
+        let skipped = p.skip(|c| {
+            if c == b'"' {
+                quotes += 1;
+            }
+            quotes < 2
+        });
+        assert_eq!((skipped, quotes), (3, 2));
+
+        let mut calls = 0;
+        assert_eq!(
+            p.expect_pred(
+                |c| {
+                    calls += 1;
+                    c == b'"'
+                },
+                MiniError::Eoi,
+            ),
+            Ok(()),
+        );
+        assert_eq!(calls, 1);
+        assert_eq!(p.position(), 4);
+    }
+
+    #[test]
+    fn predicates_may_be_function_items() {
+        fn is_space(c: u8) -> bool {
+            c == b' '
+        }
+
+        let mut p = ParserHelper::new(b"  x");
+        assert_eq!(p.skip(is_space), 2);
+        assert_eq!(
+            p.expect_pred(is_space, MiniError::msg("space")),
+            Err(Error::new(2, MiniError::msg("space"))),
+        );
+    }
+
+    #[test]
+    fn expect_found_reports_the_offset_of_the_found_byte() {
+        let mut p = ParserHelper::new(b"a:;");
+        assert_eq!(p.expect_found(b'a'), Ok::<_, Error<TestError>>(()));
+        assert_eq!(p.expect_found(b':'), Ok::<_, Error<TestError>>(()));
+        assert_eq!(p.expect_found(b':'), Err(Error::new(2, TestError::Unexpected(b';'))));
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn expect_pred_found_reports_the_offset_of_the_found_byte() {
+        let mut p = ParserHelper::new(b"1x");
+        p.advance(1);
+        assert_eq!(
+            p.expect_pred_found(|c| c.is_ascii_digit()),
+            Err(Error::new(1, TestError::Unexpected(b'x'))),
+        );
+        p.advance(1);
+        assert_eq!(p.expect_pred_found(|_| true), Err(Error::new(2, TestError::Needed(1))));
+    }
+
+    #[test]
+    fn take_reports_the_shortfall() {
+        let mut p = ParserHelper::new(b"abc");
+        assert_eq!(p.take::<TestError>(5), Err(Error::new(0, TestError::Needed(2))));
+        p.advance(1);
+        assert_eq!(p.take::<TestError>(3), Err(Error::new(1, TestError::Needed(1))));
+        p.advance(2);
+        assert_eq!(p.take::<TestError>(4), Err(Error::new(3, TestError::Needed(4))));
+        assert_eq!(p.position(), 3);
+    }
+
+    #[test]
+    fn next_array_reports_the_shortfall() {
+        let mut p = ParserHelper::new(b"abc");
+        assert_eq!(p.next_array::<8, TestError>(), Err(Error::new(0, TestError::Needed(5))));
+        p.advance(2);
+        assert_eq!(p.next_array::<2, TestError>(), Err(Error::new(2, TestError::Needed(1))));
+        assert_eq!(p.next_array::<1, TestError>(), Ok(*b"c"));
+        assert_eq!(p.next::<TestError>(), Err(Error::new(3, TestError::Needed(1))));
+    }
+
+    #[test]
+    fn failing_attempt_rewinds_and_keeps_the_error_position() {
+        let mut p = ParserHelper::new(b"abcd");
+        let result: Result<(), _> = p.attempt(|p| {
+            p.advance(3);
+            p.fail(MiniError::msg("nope"))
+        });
+        assert_eq!(result, Err(Error::new(3, MiniError::msg("nope"))));
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn nested_failing_attempt_rewinds_only_to_its_own_start() {
+        let mut p = ParserHelper::new(b"abcdef");
+        let result = p.attempt(|p| {
+            p.advance(2);
+            let inner: Result<(), _> = p.attempt(|p| {
+                p.advance(3);
+                p.fail(MiniError::msg("inner"))
+            });
+            assert_eq!(inner, Err(Error::new(5, MiniError::msg("inner"))));
+            assert_eq!(p.position(), 2);
+            p.next::<MiniError>()
+        });
+        assert_eq!(result, Ok(b'c'));
+        assert_eq!(p.position(), 3);
+
+        let result: Result<(), _> = p.attempt(|p| {
+            p.advance(1);
+            p.attempt(|p| {
+                p.advance(1);
+                p.fail(MiniError::msg("inner"))
+            })
+        });
+        assert_eq!(result, Err(Error::new(5, MiniError::msg("inner"))));
+        assert_eq!(p.position(), 3);
+    }
+
+    #[test]
+    fn optional_rewinds_a_partially_consuming_failure() {
+        let mut p = ParserHelper::new(b"abc");
+        assert_eq!(
+            p.optional(|p| {
+                p.advance(2);
+                p.fail::<(), _>(MiniError::msg("nope"))
+            }),
+            None,
+        );
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn optional_or_err_rewinds_a_partially_consuming_failure() {
+        let mut p = ParserHelper::new(b"abc");
+        assert_eq!(
+            p.optional_or_err(|p| {
+                p.advance(2);
+                p.fail::<(), _>(MiniError::msg("nope"))
+            }),
+            Ok(None),
+        );
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn optional_or_err_propagates_truncation() {
+        let mut p = ParserHelper::new(b"a");
+        assert_eq!(
+            p.optional_or_err(|p| {
+                p.next::<TestError>()?;
+                p.next::<TestError>()
+            }),
+            Err(Error::new(1, TestError::Needed(1))),
+        );
+        assert_eq!(p.position(), 0);
+
+        let mut p = ParserHelper::new(b"a");
+        assert_eq!(
+            p.optional_or_err(|p| p.fail::<(), _>(TestError::eoi())),
+            Err(Error::new(0, TestError::Eoi)),
+        );
+    }
+
+    #[test]
+    fn fold_many0_folds_until_an_item_fails() {
+        let mut p = ParserHelper::new(b"12x");
+        assert_eq!(p.fold_many0(digit, 0, |n, _| n + 1), Ok(2));
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn fold_many0_rejects_an_item_that_consumes_nothing() {
+        let mut p = ParserHelper::new(b"  x");
+        let spaces = |p: &mut ParserHelper| Ok::<_, Error<MiniError>>(p.skip(|c| c == b' '));
+        assert_eq!(
+            p.fold_many0(spaces, 0, |n, spaces| n + spaces),
+            Err(Error::new(2, MiniError::no_progress())),
+        );
+    }
+
+    #[test]
+    fn fold_many1_rejects_a_first_item_that_consumes_nothing() {
+        let mut p = ParserHelper::new(b"x");
+        let spaces = |p: &mut ParserHelper| Ok::<_, Error<MiniError>>(p.skip(|c| c == b' '));
+        assert_eq!(
+            p.fold_many1(spaces, 0, |n, _| n + 1),
+            Err(Error::new(0, MiniError::no_progress())),
+        );
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn fold_many1_requires_one_item() {
+        let mut p = ParserHelper::new(b"x");
+        assert_eq!(
+            p.fold_many1(|p| p.expect_bytes(b"a", MiniError::msg("a")), 0, |n, _| n + 1),
+            Err(Error::new(0, MiniError::msg("a"))),
+        );
+    }
+
+    fn digit(p: &mut ParserHelper) -> Result<u8, Error<MiniError>> {
+        p.expect_byte_range(b'0', b'9', MiniError::msg("digit"))
+    }
+
+    fn comma(p: &mut ParserHelper) -> Result<(), Error<MiniError>> {
+        p.expect_bytes(b",", MiniError::msg("comma"))
+    }
+
+    #[test]
+    fn separated_empty_list() {
+        for trailing in [TrailingSep::Forbidden, TrailingSep::Allowed, TrailingSep::Required] {
+            let mut p = ParserHelper::new(b"]");
+            let mut items = Vec::new();
+            assert_eq!(p.separated(digit, comma, trailing, |d| items.push(d)), Ok(0));
+            assert!(items.is_empty());
+            assert_eq!(p.position(), 0);
+        }
+    }
+
+    #[test]
+    fn separated_single_element_list() {
+        let mut p = ParserHelper::new(b"1]");
+        let mut items = Vec::new();
+        assert_eq!(p.separated(digit, comma, TrailingSep::Forbidden, |d| items.push(d)), Ok(1));
+        assert_eq!(items, [b'1']);
+        assert_eq!(p.position(), 1);
+
+        let mut p = ParserHelper::new(b"1]");
+        assert_eq!(
+            p.separated(digit, comma, TrailingSep::Required, |_| {}),
+            Err(Error::new(1, MiniError::msg("comma"))),
+        );
+    }
+
+    #[test]
+    fn separated_single_element_list_with_trailing_separator() {
+        let mut p = ParserHelper::new(b"1,]");
+        assert_eq!(p.separated(digit, comma, TrailingSep::Forbidden, |_| {}), Ok(1));
+        assert_eq!(p.position(), 1);
+
+        for trailing in [TrailingSep::Allowed, TrailingSep::Required] {
+            let mut p = ParserHelper::new(b"1,]");
+            assert_eq!(p.separated(digit, comma, trailing, |_| {}), Ok(1));
+            assert_eq!(p.position(), 2);
+        }
+    }
+
+    #[test]
+    fn separated_stops_when_nothing_is_consumed() {
+        let mut p = ParserHelper::new(b"1]");
+        let nothing = |_: &mut ParserHelper| Ok::<_, Error<MiniError>>(());
+        assert_eq!(p.separated(nothing, nothing, TrailingSep::Forbidden, |_| {}), Ok(1));
+        assert_eq!(p.position(), 0);
+    }
+
+    fn end(p: &mut ParserHelper) -> Result<(), Error<MiniError>> {
+        p.expect_bytes(b"END", MiniError::msg("end"))
+    }
+
+    #[test]
+    fn many_till_without_items() {
+        let mut p = ParserHelper::new(b"END;");
+        assert_eq!(p.many_till(digit, end, |_| {}), Ok(0));
+        assert_eq!(p.position(), 3);
+    }
+
+    #[test]
+    fn many_till_without_terminator_propagates_eoi() {
+        let mut p = ParserHelper::new(b"12");
+        let mut items = Vec::new();
+        assert_eq!(
+            p.many_till(|p| p.next(), end, |d| items.push(d)),
+            Err(Error::new(2, MiniError::Eoi)),
+        );
+        assert_eq!(items, b"12");
+    }
+
+    #[test]
+    fn many_till_returns_the_item_error() {
+        let mut p = ParserHelper::new(b"1x");
+        assert_eq!(p.many_till(digit, end, |_| {}), Err(Error::new(1, MiniError::msg("digit"))));
+        assert_eq!(p.position(), 1);
+    }
+
+    #[test]
+    fn many_till_stops_when_an_item_consumes_nothing() {
+        let mut p = ParserHelper::new(b"1x");
+        let nothing = |_: &mut ParserHelper| Ok::<_, Error<MiniError>>(());
+        assert_eq!(p.many_till(nothing, end, |_| {}), Err(Error::new(0, MiniError::msg("end"))));
+        assert_eq!(p.position(), 0);
+    }
+
+    #[test]
+    fn consumed_interleaved_with_next() {
+        let mut p = ParserHelper::new(b"ab");
+        assert_eq!(p.consumed(), b"");
+        assert_eq!(p.next::<MiniError>(), Ok(b'a'));
+        assert_eq!(p.consumed(), b"a");
+        assert_eq!(p.next::<MiniError>(), Ok(b'b'));
+        assert_eq!(p.consumed(), b"ab");
+        assert!(p.next::<MiniError>().is_err());
+        assert_eq!(p.consumed(), b"ab");
+    }
+
+    #[test]
+    fn consumed_is_clamped_to_the_input() {
+        let mut p = ParserHelper::new(b"ab");
+        p.advance(5);
+        assert_eq!(p.consumed(), b"ab");
+    }
+
+    #[test]
+    fn line_col_on_the_first_line() {
+        let mut p = ParserHelper::new(b"abc\ndef");
+        let mut s = p.with_line_col_tracker();
+        assert_eq!(s.current_line_col(), (1, 1));
+        s.advance(2);
+        let err = s.fail_with_location::<(), _>(MiniError::Eoi).unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.line_col, LineColPosition { line: 1, col: 3 });
+    }
+
+    #[test]
+    fn line_col_just_after_a_newline() {
+        let mut p = ParserHelper::new(b"ab\ncd\r\nef");
+        let mut s = p.with_line_col_tracker();
+        s.advance(3);
+        assert_eq!(s.current_line_col(), (2, 1));
+        s.advance(4);
+        assert_eq!(s.current_line_col(), (3, 1));
+    }
+
+    #[test]
+    fn line_col_at_the_end_of_input() {
+        let mut p = ParserHelper::new(b"ab\ncd");
+        let mut s = p.with_line_col_tracker();
+        s.advance(5);
+        let err = s.fail_with_location::<(), _>(MiniError::Eoi).unwrap_err();
+        assert_eq!(err.position, 5);
+        assert_eq!(err.line_col, LineColPosition { line: 2, col: 3 });
+    }
+
+    #[test]
+    fn line_col_after_backtracking() {
+        let mut p = ParserHelper::new(b"a\nb\nc");
+        let mut s = p.with_line_col_tracker();
+        s.advance(4);
+        assert_eq!(s.current_line_col(), (3, 1));
+        s.advance_back(2);
+        assert_eq!(s.current_line_col(), (2, 1));
+    }
+
+    #[test]
+    fn parse_decimal_distinguishes_eoi_from_non_digits() {
+        let unexpected = Err(Error::new(0, TestError::Unexpected(b'a')));
+        assert_eq!(parsed(b"abc", ParserHelper::parse_u64_decimal), (unexpected, 0));
+        let eoi = Err(Error::new(0, TestError::Needed(1)));
+        assert_eq!(parsed(b"", ParserHelper::parse_u64_decimal), (eoi, 0));
+        let eoi = Err(Error::new(1, TestError::Needed(1)));
+        assert_eq!(parsed(b"-", ParserHelper::parse_i64_decimal), (eoi, 0));
+    }
+
+    /// Run `f` on `input`, and return its result together with the position it stopped at.
+    fn parsed<'a, T>(
+        input: &'a [u8],
+        f: impl FnOnce(&mut ParserHelper<'a>) -> Result<T, Error<TestError>>,
+    ) -> (Result<T, Error<TestError>>, usize) {
+        let mut p = ParserHelper::new(input);
+        let result = f(&mut p);
+        (result, p.position())
+    }
+
+    #[test]
+    fn parse_u64_decimal_boundaries() {
+        let max = b"18446744073709551615";
+        assert_eq!(parsed(max, ParserHelper::parse_u64_decimal), (Ok(u64::MAX), 20));
+        let overflow = Err(Error::new(0, TestError::Overflow));
+        let above_max = b"18446744073709551616";
+        assert_eq!(parsed(above_max, ParserHelper::parse_u64_decimal), (overflow, 0));
+        assert_eq!(parsed(b"007,", ParserHelper::parse_u64_decimal), (Ok(7), 3));
+    }
+
+    #[test]
+    fn parse_i64_decimal_boundaries() {
+        let min = b"-9223372036854775808";
+        assert_eq!(parsed(min, ParserHelper::parse_i64_decimal), (Ok(i64::MIN), 20));
+        let max = b"+9223372036854775807";
+        assert_eq!(parsed(max, ParserHelper::parse_i64_decimal), (Ok(i64::MAX), 20));
+        let max = b"9223372036854775807";
+        assert_eq!(parsed(max, ParserHelper::parse_i64_decimal), (Ok(i64::MAX), 19));
+    }
+
+    #[test]
+    fn parse_i64_decimal_reports_overflow_at_the_sign() {
+        let overflow = Err(Error::new(0, TestError::Overflow));
+        let above_max = b"+9223372036854775808";
+        assert_eq!(parsed(above_max, ParserHelper::parse_i64_decimal), (overflow, 0));
+        let below_min = b"-9223372036854775809";
+        assert_eq!(parsed(below_min, ParserHelper::parse_i64_decimal), (overflow, 0));
+        let above_max = b"9223372036854775808";
+        assert_eq!(parsed(above_max, ParserHelper::parse_i64_decimal), (overflow, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "radix 1 is not between 2 and 36")]
+    fn parse_radix_int_rejects_radix_one() {
+        let mut p = ParserHelper::new(b"0");
+        let _ = p.parse_radix_int::<TestError>(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "radix 37 is not between 2 and 36")]
+    fn parse_radix_int_rejects_radix_above_36() {
+        let mut p = ParserHelper::new(b"0");
+        let _ = p.parse_radix_int::<TestError>(37);
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn nested(p: &mut ParserHelper, depth: usize) -> Result<u8, ErrorContext<MiniError>> {
+        match depth {
+            0 => Ok(p.next()?),
+            _ => p.within("level", |p| {
+                p.advance(1);
+                nested(p, depth - 1)
+            }),
+        }
+    }
+
+    #[test]
+    fn context_frames_are_inline_up_to_four() {
+        let err = nested(&mut ParserHelper::new(b"abcd"), 4).unwrap_err();
+        assert_eq!(err.context.len(), 4);
+        assert_eq!(err.context.outer.capacity(), 0);
+    }
+
+    #[test]
+    fn context_frames_keep_the_order_beyond_four() {
+        let err = nested(&mut ParserHelper::new(b"abcdef"), 6).unwrap_err();
+        let positions: Vec<_> = err.context.iter().map(|(_, position)| position).collect();
+        assert_eq!(positions, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn parser_helper_is_send_and_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<ParserHelper<'static>>();
+    }
+
+    #[test]
+    fn furthest_error_survives_backtracking() {
+        let mut p = ParserHelper::new(b"abc");
+        let mut s = p.with_furthest_error::<TestError>();
+        let res = s.attempt(|p| {
+            p.advance(2);
+            p.expect_found::<TestError>(b'x')
+        });
+        assert!(s.track(res).is_err());
+        assert_eq!(s.position(), 0);
+        assert!(s.fail::<()>(TestError::Unexpected(b'a')).is_err());
+        assert_eq!(s.furthest_error(), Some(&Error::new(2, TestError::Unexpected(b'c'))));
+    }
+
+    #[test]
+    fn furthest_error_keeps_the_first_at_a_position() {
+        let mut p = ParserHelper::new(b"abc");
+        let mut s = p.with_furthest_error::<TestError>();
+        assert!(s.fail::<()>(TestError::Unexpected(b'a')).is_err());
+        assert!(s.unexpected_end_of_input::<()>().is_err());
+        assert_eq!(s.furthest_error(), Some(&Error::new(0, TestError::Unexpected(b'a'))));
+    }
+
+    #[test]
+    fn furthest_error_covers_spans() {
+        let mut p = ParserHelper::new(b"abc");
+        let mut s = p.with_furthest_error::<TestError>();
+        s.advance(3);
+        assert!(s.fail_spanning::<()>(1, TestError::Eoi).is_err());
+        assert_eq!(s.furthest_failure(), Some(3));
+        assert!(s.fail_spanned::<()>(TestError::Eoi, 0, 1).is_err());
+        assert_eq!(s.take_furthest_error(), Some(Error::new(3, TestError::Eoi)));
+        assert_eq!(s.furthest_failure(), None);
+    }
+
+    #[test]
+    fn expectations_are_kept_when_backtracking_without_progress() {
+        let mut p = ParserHelper::new(b"[1x");
+        let mut p = p.with_expectations();
+        p.advance(2);
+        assert!(p.expect_labeled::<TestError>(b',', "','").is_err());
+        p.reset_position(0);
+        assert!(p.expect_labeled::<TestError>(b'{', "'{'").is_err());
+        assert_eq!(p.expectations(), ["','"]);
+    }
+
+    #[test]
+    fn expectations_are_reset_after_progress() {
+        let mut p = ParserHelper::new(b"[1x");
+        let mut p = p.with_expectations();
+        p.advance(2);
+        assert!(p.expect_labeled::<TestError>(b',', "','").is_err());
+        p.advance(1);
+        assert!(p.expectations().is_empty());
+        assert!(p.expect_labeled::<TestError>(b']', "']'").is_err());
+        assert_eq!(p.expectations(), ["']'"]);
+    }
+
+    #[test]
+    fn expectations_are_reset_after_progress_and_backtracking() {
+        let mut p = ParserHelper::new(b"[1x!");
+        let mut p = p.with_expectations();
+        p.advance(2);
+        assert!(p.expect_labeled::<TestError>(b',', "','").is_err());
+        p.advance(1);
+        p.reset_position(0);
+        assert!(p.expect_labeled::<TestError>(b'{', "'{'").is_err());
+        assert_eq!(p.expectations(), ["'{'"]);
+    }
+
+    #[test]
+    fn peek_ahead_past_the_end() {
+        let mut p = ParserHelper::new(b"ab");
+        p.advance(3);
+        assert_eq!(p.peek_ahead::<TestError>(0), p.peek::<TestError>());
+        assert_eq!(p.peek_ahead::<TestError>(1), Err(Error::new(3, TestError::Needed(2))));
+        assert_eq!(p.peek_ahead_or_end(0), None);
+    }
+
+    #[test]
+    fn take_remaining_past_the_end() {
+        let mut p = ParserHelper::new(b"ab");
+        p.advance(3);
+        assert_eq!(p.take_remaining(), b"");
+        assert_eq!(p.position(), 2);
+    }
+
+    #[test]
+    fn eoi_without_is_eoi_still_works() {
+        #[derive(Debug, PartialEq)]
+        struct OnlyEoi;
+
+        impl Eoi for OnlyEoi {
+            fn eoi() -> Self {
+                OnlyEoi
+            }
+        }
+
+        let mut p = ParserHelper::new(b"");
+        assert_eq!(p.next::<OnlyEoi>(), Err(Error::new(0, OnlyEoi)));
+        assert_eq!(p.take::<OnlyEoi>(2), Err(Error::new(0, OnlyEoi)));
+    }
+
+    #[test]
+    fn reversed_span_is_empty() {
+        let span = Span { start: 2, end: 1 };
+        assert_eq!(span.len(), 0);
+        assert_eq!(span.slice_of(b"abc"), b"");
+    }
+
+    #[test]
+    fn records_yield_one_item_per_record() {
+        let records: Vec<_> = ParserHelper::new(b"123").records(digit).collect();
+        assert_eq!(records, [Ok(b'1'), Ok(b'2'), Ok(b'3')]);
+    }
+
+    #[test]
+    fn records_stop_after_an_error() {
+        let mut records = ParserHelper::new(b"1x2").records(digit);
+        assert_eq!(records.next(), Some(Ok(b'1')));
+        assert_eq!(records.next(), Some(Err(Error::new(1, MiniError::msg("digit")))));
+        assert_eq!(records.next(), None);
+    }
+
+    #[test]
+    fn records_reject_a_record_that_consumes_nothing() {
+        let spaces = |p: &mut ParserHelper| Ok::<_, Error<MiniError>>(p.skip(|c| c == b' '));
+        let mut records = ParserHelper::new(b" x").records(spaces);
+        assert_eq!(records.next(), Some(Ok(1)));
+        assert_eq!(records.next(), Some(Err(Error::new(1, MiniError::no_progress()))));
+        assert_eq!(records.next(), None);
+    }
+
+    fn digit_list_recovering(input: &[u8]) -> (Vec<u8>, ErrorAccumulator<MiniError>) {
+        let mut p = ParserHelper::new(input);
+        let mut acc = ErrorAccumulator::new();
+        let mut digits = Vec::new();
+        while p.has_remaining() {
+            let item = |p: &mut ParserHelper| {
+                let d = digit(p)?;
+                if p.has_remaining() {
+                    p.expect_bytes(b";", MiniError::msg("semicolon"))?;
+                }
+                Ok(d)
+            };
+            let skip_to_next = |p: &mut ParserHelper| {
+                p.skip(|c| c != b';');
+                p.advance_over(b";");
+            };
+            digits.extend(p.recovering(item, skip_to_next, &mut acc));
+        }
+        (digits, acc)
+    }
+
+    #[test]
+    fn recovering_without_errors() {
+        let (digits, acc) = digit_list_recovering(b"1;2;3");
+        assert_eq!(digits, b"123");
+        assert!(acc.is_ok());
+        assert_eq!(acc.errors(), []);
+    }
+
+    #[test]
+    fn recovering_accumulates_errors_and_continues() {
+        let (digits, acc) = digit_list_recovering(b"1;x;3yy;5");
+        assert_eq!(digits, b"15");
+        assert!(!acc.is_ok());
+        assert_eq!(
+            acc.into_errors(),
+            [Error::new(2, MiniError::msg("digit")), Error::new(5, MiniError::msg("semicolon"))],
+        );
+    }
+
+    #[test]
+    fn line_index_with_a_trailing_newline() {
+        let index = LineIndex::new(b"a\n");
+        assert_eq!(index.location(1), (1, 2));
+        assert_eq!(index.location(2), (2, 1));
+        assert_eq!(Error::new(7, MiniError::Eoi).locate(&index), (2, 1));
+    }
+
+    #[test]
+    fn line_index_of_empty_input() {
+        let index = LineIndex::new(b"");
+        assert_eq!(index.location(0), (1, 1));
+        assert_eq!(index.location(1), (1, 1));
+    }
+
+    #[test]
+    fn parse_hex_boundaries() {
+        assert_eq!(parsed(b"fFfFfFfF", ParserHelper::parse_u32_hex), (Ok(u32::MAX), 8));
+        let overflow = Err(Error::new(0, TestError::Overflow));
+        assert_eq!(parsed(b"100000000", ParserHelper::parse_u32_hex), (overflow, 0));
+        assert_eq!(parsed(b"100000000", ParserHelper::parse_u64_hex), (Ok(1 << 32), 9));
+        assert_eq!(parsed(b"1fg", ParserHelper::parse_u64_hex), (Ok(0x1f), 2));
+    }
+
+    #[test]
+    fn parse_octal_stops_at_eight() {
+        assert_eq!(parsed(b"178", ParserHelper::parse_u32_octal), (Ok(0o17), 2));
+        let unexpected = Err(Error::new(0, TestError::Unexpected(b'8')));
+        assert_eq!(parsed(b"8", ParserHelper::parse_u32_octal), (unexpected, 0));
+    }
+
+    #[test]
+    fn parse_binary_boundaries() {
+        let max = [b'1'; 32];
+        assert_eq!(parsed(&max, ParserHelper::parse_u32_binary), (Ok(u32::MAX), 32));
+        let mut above_max = [b'0'; 33];
+        above_max[0] = b'1';
+        let overflow = Err(Error::new(0, TestError::Overflow));
+        assert_eq!(parsed(&above_max, ParserHelper::parse_u32_binary), (overflow, 0));
+    }
+
+    #[test]
+    fn parse_radix_36() {
+        let radix_36 = |p: &mut ParserHelper| p.parse_radix_int(36);
+        assert_eq!(parsed(b"zZ!", radix_36), (Ok(35 * 36 + 35), 2));
+        let max = b"3w5e11264sgsf";
+        assert_eq!(parsed(max, |p| p.parse_radix_int(36)), (Ok(u64::MAX), 13));
+        let overflow = Err(Error::new(0, TestError::Overflow));
+        assert_eq!(parsed(b"3w5e11264sgsg", |p| p.parse_radix_int(36)), (overflow, 0));
+    }
+
+    fn expecting_comma_or_bracket<'p, 'a>(
+        p: &'p mut ParserHelper<'a>,
+    ) -> ExpectationsSession<'p, 'a> {
+        let mut p = p.with_expectations();
+        p.advance(2);
+        assert!(p.expect_labeled::<TestError>(b',', "','").is_err());
+        assert!(p.expect_labeled::<TestError>(b']', "']'").is_err());
+        p
+    }
+
+    #[test]
+    fn expectations_survive_a_failing_tentative_consumer() {
+        let mut p = ParserHelper::new(b"[1aax");
+        let mut p = expecting_comma_or_bracket(&mut p);
+        assert!(p.skip_at_least(|c| c == b'a', 3, TestError::Eoi).is_err());
+        assert_eq!(p.position(), 2);
+        assert_eq!(p.expectations(), ["','", "']'"]);
+    }
+
+    #[test]
+    fn expectations_survive_a_failing_attempt() {
+        let mut p = ParserHelper::new(b"[1aax");
+        let mut p = expecting_comma_or_bracket(&mut p);
+        let res = p.attempt(|p| {
+            p.advance(2);
+            p.fail::<(), _>(TestError::Eoi)
+        });
+        assert!(res.is_err());
+        assert!(p.expect_labeled::<TestError>(b'}', "'}'").is_err());
+        assert_eq!(p.expectations(), ["','", "']'", "'}'"]);
+    }
+
+    #[test]
+    fn expectations_are_reset_after_a_successful_attempt() {
+        let mut p = ParserHelper::new(b"[1aax");
+        let mut p = expecting_comma_or_bracket(&mut p);
+        assert_eq!(p.attempt(|p| p.skip_at_least(|c| c == b'a', 2, TestError::Eoi)), Ok(2));
+        p.reset_position(2);
+        assert!(p.expect_labeled::<TestError>(b'}', "'}'").is_err());
+        assert_eq!(p.expectations(), ["'}'"]);
+    }
 }
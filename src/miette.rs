@@ -0,0 +1,84 @@
+//! Reporting parse errors through `miette`.
+//!
+//! ```
+//! use atm_parser_helper::{MiniError, ParserHelper};
+//! use miette::{GraphicalReportHandler, GraphicalTheme, NamedSource};
+//!
+//! fn parse(name: &str, input: &str) -> miette::Result<()> {
+//!     let mut p = ParserHelper::new(input.as_bytes());
+//!     if let Err(err) = p.expect_bytes(b"true", MiniError::msg("expected `true`")) {
+//!         Err(err.into_miette(NamedSource::new(name, input.to_string())))?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! let report = parse("foo.txt", "tru").unwrap_err();
+//! let mut out = String::new();
+//! GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+//!     .render_report(&mut out, report.as_ref())
+//!     .unwrap();
+//! assert!(out.contains("expected `true`"));
+//! assert!(out.contains("[foo.txt:1:1]"));
+//! ```
+use std::fmt;
+
+use ::miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode, SourceSpan};
+
+use crate::{Error, ErrorSpan};
+
+/// A parse error together with the input it occurred in, implementing `miette::Diagnostic`.
+/// Obtained via `Error::into_miette` or `ErrorSpan::into_miette`. Displays only the inner error,
+/// since the report shows the location.
+#[derive(Debug)]
+pub struct MietteError<E> {
+    pub e: E,
+    pub span: SourceSpan,
+    pub src: NamedSource<String>,
+}
+
+impl<E> Error<E> {
+    /// Attach the input the error was produced from, for reporting through `miette`. The error
+    /// is labeled at the byte at its position, so positions have to be valid byte offsets of `src`.
+    pub fn into_miette(self, src: NamedSource<String>) -> MietteError<E> {
+        let len = if self.position < src.inner().len() {
+            1
+        } else {
+            0
+        };
+        MietteError {
+            e: self.e,
+            span: (self.position, len).into(),
+            src,
+        }
+    }
+}
+
+impl<E> ErrorSpan<E> {
+    /// Attach the input the error was produced from, for reporting through `miette`. The error
+    /// is labeled at its span, so positions have to be valid byte offsets of `src`.
+    pub fn into_miette(self, src: NamedSource<String>) -> MietteError<E> {
+        MietteError {
+            e: self.e,
+            span: (self.start, self.end.saturating_sub(self.start)).into(),
+            src,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for MietteError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.e)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for MietteError<E> {}
+
+impl<E: fmt::Debug + fmt::Display> Diagnostic for MietteError<E> {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::underline(self.span))))
+    }
+}